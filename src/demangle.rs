@@ -0,0 +1,775 @@
+/*
+
+BSD 3-Clause License
+
+Copyright (c) 2025, Isaac Budzik
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+3. Neither the name of the copyright holder nor the names of its
+   contributors may be used to endorse or promote products derived from
+   this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+ */
+
+
+//! Parse symbols mangled according to the Itanium C++ ABI back into a Func
+
+use core::fmt::{self, Display, Formatter};
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::vec;
+use alloc::boxed::Box;
+
+use super::{
+    ArrayBound,
+    Component,
+    CtorKind,
+    DtorKind,
+    Func,
+    FunctionType,
+    Operator,
+    Qualifiers,
+    RefQualifier,
+    Scope,
+    TemplateArg,
+    Type,
+    UnqualifiedName,
+    std_substitution
+};
+
+/// Reason a symbol could not be demangled, offsets are in bytes from the start of the symbol
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum DemangleError {
+    /// symbol does not start with _Z
+    NotMangled,
+    /// symbol ended in the middle of the encoding
+    UnexpectedEnd,
+    /// unexpected character at offset
+    Unexpected(usize),
+    /// substitution at offset refers past the end of the substitution table
+    BadSubstitution(usize),
+    /// identifier at offset is not valid UTF-8
+    InvalidIdentifier(usize),
+    /// type at offset is nested deeper than the parser allows
+    TooDeep(usize)
+}
+
+impl Display for DemangleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    DemangleError::NotMangled => f.write_str("symbol is not mangled"),
+	    DemangleError::UnexpectedEnd => f.write_str("unexpected end of symbol"),
+	    DemangleError::Unexpected(n) => write!(f, "unexpected character at offset {}", n),
+	    DemangleError::BadSubstitution(n) => write!(f, "invalid substitution at offset {}", n),
+	    DemangleError::InvalidIdentifier(n) => write!(f, "invalid identifier at offset {}", n),
+	    DemangleError::TooDeep(n) => write!(f, "type nested too deeply at offset {}", n)
+	}
+    }
+}
+
+impl core::error::Error for DemangleError {}
+
+/// Demangle function symbol according to Itanium C++ ABI
+///
+/// sym - mangled symbol, e.g. _ZN2ns4funcEi
+///
+/// clone suffixes GCC appends to copies of a function, as in _Z3foov.cold, are ignored
+pub fn demangle(sym: &str) -> Result<Func, DemangleError> {
+    if !sym.starts_with("_Z") {
+	return Err(DemangleError::NotMangled);
+    }
+    let mut p = Parser {
+	s: sym.as_bytes(),
+	pos: 2,
+	subs: Vec::new(),
+	depth: 0
+    };
+    p.encoding()
+}
+
+/// Types may nest no deeper than this, so that untrusted symbols cannot overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// Recursive descent parser mirroring the substitution table of the mangler
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    subs: Vec<Type>,
+    /// number of types being parsed, every recursion goes through a type
+    depth: usize
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+	self.s.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, DemangleError> {
+	let c = self.peek().ok_or(DemangleError::UnexpectedEnd)?;
+	self.pos += 1;
+	Ok(c)
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+	if self.peek() == Some(c) {
+	    self.pos += 1;
+	    true
+	} else {
+	    false
+	}
+    }
+
+    /// <encoding> ::= <name> <bare-function-type>
+    fn encoding(&mut self) -> Result<Func, DemangleError> {
+	// only nested names carry the qualifiers of a member function
+	let (scope, quals, ref_qual) = if self.eat(b'N') {
+	    let quals = self.qualifiers();
+	    let ref_qual = if self.eat(b'R') {
+		Some(RefQualifier::LValue)
+	    } else if self.eat(b'O') {
+		Some(RefQualifier::RValue)
+	    } else {
+		None
+	    };
+	    (self.nested_name(false)?, quals, ref_qual)
+	} else {
+	    (self.name(false)?, Qualifiers::NONE, None)
+	};
+	// function templates encode their return type, void being the default
+	let ret = match scope.is_template() && !scope.omits_return() {
+	    true => Some(self.ty()?).filter(|r| *r != Type::Void),
+	    false => None
+	};
+	let mut params = Vec::new();
+	while self.peek().is_some_and(|c| c != b'.') {
+	    params.push(self.ty()?);
+	}
+	if params.is_empty() {
+	    return Err(DemangleError::UnexpectedEnd);
+	}
+	self.clone_suffix()?;
+	Ok(Func {
+	    scope,
+	    ret,
+	    params,
+	    quals,
+	    ref_qual,
+	    std_lib: None
+	})
+    }
+
+    /// clone suffixes such as .isra.0, each a . followed by letters, digits or _
+    fn clone_suffix(&mut self) -> Result<(), DemangleError> {
+	while self.eat(b'.') {
+	    let start = self.pos;
+	    while let Some(b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_') = self.peek() {
+		self.pos += 1;
+	    }
+	    if self.pos == start {
+		return Err(match self.peek() {
+		    Some(_) => DemangleError::Unexpected(start),
+		    None => DemangleError::UnexpectedEnd
+		});
+	    }
+	}
+	match self.peek() {
+	    Some(_) => Err(DemangleError::Unexpected(self.pos)),
+	    None => Ok(())
+	}
+    }
+
+    fn number(&mut self) -> Result<usize, DemangleError> {
+	let start = self.pos;
+	let mut n: usize = 0;
+	while let Some(c @ b'0'..=b'9') = self.peek() {
+	    n = n.checked_mul(10)
+		.and_then(|n| n.checked_add((c - b'0') as usize))
+		.ok_or(DemangleError::Unexpected(self.pos))?;
+	    self.pos += 1;
+	}
+	if self.pos == start {
+	    return Err(match self.peek() {
+		Some(_) => DemangleError::Unexpected(start),
+		None => DemangleError::UnexpectedEnd
+	    });
+	}
+	Ok(n)
+    }
+
+    /// <template-param> ::= T_ | T <index - 1> _
+    fn template_param(&mut self) -> Result<u32, DemangleError> {
+	let start = self.pos;
+	self.pos += 1;
+	let n = if self.peek() == Some(b'_') { 0 } else { self.number()?.saturating_add(1) };
+	if !self.eat(b'_') {
+	    return Err(DemangleError::Unexpected(self.pos));
+	}
+	u32::try_from(n).map_err(|_| DemangleError::Unexpected(start))
+    }
+
+    /// <source-name> ::= <length> <identifier>
+    fn source_name(&mut self) -> Result<String, DemangleError> {
+	// the mangler never emits empty identifiers or leading zeros
+	if self.peek() == Some(b'0') {
+	    return Err(DemangleError::Unexpected(self.pos));
+	}
+	let n = self.number()?;
+	let start = self.pos;
+	let end = start.checked_add(n).filter(|e| *e <= self.s.len()).ok_or(DemangleError::UnexpectedEnd)?;
+	self.pos = end;
+	core::str::from_utf8(&self.s[start..end])
+	    .map(String::from)
+	    .map_err(|_| DemangleError::InvalidIdentifier(start))
+    }
+
+    /// name of a function or, if is_type, of a class or enum
+    fn name(&mut self, is_type: bool) -> Result<Scope, DemangleError> {
+	match self.peek() {
+	    Some(b'N') => {
+		self.pos += 1;
+		self.nested_name(is_type)
+	    },
+	    // operator names only name functions
+	    Some(c) if c.is_ascii_digit() || c == b'L' || (!is_type && c.is_ascii_lowercase()) => {
+		let mut c = Vec::new();
+		self.component(&mut c, is_type, false)?;
+		Ok(Scope::from_components(c))
+	    },
+	    Some(b'S') if self.is_std() => {
+		self.pos += 2;
+		let mut c = vec![Component::new(String::from("std"))];
+		self.component(&mut c, is_type, false)?;
+		Ok(Scope::from_components(c))
+	    },
+	    Some(_) => Err(DemangleError::Unexpected(self.pos)),
+	    None => Err(DemangleError::UnexpectedEnd)
+	}
+    }
+
+    /// <nested-name> ::= N [<substitution>] <prefix>+ E
+    fn nested_name(&mut self, is_type: bool) -> Result<Scope, DemangleError> {
+	let mut c = Vec::new();
+	if self.is_std() {
+	    self.pos += 2;
+	    c.push(Component::new(String::from("std")));
+	} else if self.peek() == Some(b'S') {
+	    let start = self.pos;
+	    match self.substitution()? {
+		Type::Named(n) => c.extend_from_slice(n.components()),
+		_ => return Err(DemangleError::BadSubstitution(start))
+	    }
+	    self.component_args(&mut c, is_type, true)?;
+	}
+	while !self.eat(b'E') {
+	    self.component(&mut c, is_type, true)?;
+	}
+	if c.len() < 2 {
+	    return Err(DemangleError::Unexpected(self.pos - 1));
+	}
+	Ok(Scope::Nested(c))
+    }
+
+    /// whether St, the prefix of names in std, is next
+    fn is_std(&self) -> bool {
+	self.s.get(self.pos..self.pos + 2) == Some(b"St")
+    }
+
+    /// whether the name being parsed ends here
+    fn at_end(&self, nested: bool) -> bool {
+	!nested || self.peek() == Some(b'E')
+    }
+
+    /// record prefix, the function name itself is not a candidate
+    fn add_prefix(&mut self, c: &[Component], is_type: bool, nested: bool) {
+	if is_type || !self.at_end(nested) {
+	    self.subs.push(Type::Named(Scope::from_components(c.to_vec())));
+	}
+    }
+
+    /// <unqualified-name> [<abi-tags>] [<template-args>] appended to c
+    fn component(&mut self, c: &mut Vec<Component>, is_type: bool, nested: bool) -> Result<(), DemangleError> {
+	let name = self.unqualified_name()?;
+	// constructors and destructors need an enclosing class
+	if matches!(name, UnqualifiedName::Ctor(_) | UnqualifiedName::Dtor(_)) && c.is_empty() {
+	    return Err(DemangleError::Unexpected(self.pos - 2));
+	}
+	let mut tags = Vec::new();
+	while self.eat(b'B') {
+	    tags.push(self.source_name()?);
+	}
+	c.push(Component::from(name).with_abi_tags(tags));
+	// a template name is always followed by its arguments
+	if self.peek() == Some(b'I') {
+	    self.subs.push(Type::Named(Scope::from_components(c.clone())));
+	} else {
+	    self.add_prefix(c, is_type, nested);
+	}
+	self.component_args(c, is_type, nested)
+    }
+
+    /// <unqualified-name>
+    fn unqualified_name(&mut self) -> Result<UnqualifiedName, DemangleError> {
+	let start = self.pos;
+	Ok(match self.peek() {
+	    Some(b'C') | Some(b'D') => {
+		let kind = self.next()?;
+		match (kind, self.next()?) {
+		    (b'C', b'1') => UnqualifiedName::Ctor(CtorKind::Complete),
+		    (b'C', b'2') => UnqualifiedName::Ctor(CtorKind::Base),
+		    (b'C', b'3') => UnqualifiedName::Ctor(CtorKind::Allocating),
+		    (b'D', b'0') => UnqualifiedName::Dtor(DtorKind::Deleting),
+		    (b'D', b'1') => UnqualifiedName::Dtor(DtorKind::Complete),
+		    (b'D', b'2') => UnqualifiedName::Dtor(DtorKind::Base),
+		    _ => return Err(DemangleError::Unexpected(start))
+		}
+	    },
+	    Some(b'c') if self.s.get(self.pos + 1) == Some(&b'v') => {
+		self.pos += 2;
+		UnqualifiedName::Conversion(Box::new(self.ty()?))
+	    },
+	    Some(b'l') if self.s.get(self.pos + 1) == Some(&b'i') => {
+		self.pos += 2;
+		UnqualifiedName::Literal(self.source_name()?)
+	    },
+	    Some(b'a'..=b'z') => {
+		let code = self.s.get(start..start + 2).ok_or(DemangleError::UnexpectedEnd)?;
+		let o = Operator::from_code(code).ok_or(DemangleError::Unexpected(start))?;
+		self.pos += 2;
+		UnqualifiedName::Operator(o)
+	    },
+	    Some(b'L') => {
+		self.pos += 1;
+		UnqualifiedName::Internal(self.source_name()?)
+	    },
+	    _ => {
+		let n = self.source_name()?;
+		// _GLOBAL_ followed by one of . _ $ and N, as c++filt recognizes
+		match n.strip_prefix("_GLOBAL_").map(str::as_bytes) {
+		    Some([b'.' | b'_' | b'$', b'N', ..]) => UnqualifiedName::AnonymousNamespace,
+		    _ => UnqualifiedName::Source(n)
+		}
+	    }
+	})
+    }
+
+    /// template arguments of the final component of c, if any
+    fn component_args(&mut self, c: &mut [Component], is_type: bool, nested: bool) -> Result<(), DemangleError> {
+	if self.eat(b'I') {
+	    let args = self.template_args()?;
+	    if let Some(l) = c.last_mut() {
+		l.template_args = Some(args);
+	    }
+	    self.add_prefix(c, is_type, nested);
+	}
+	Ok(())
+    }
+
+    /// <template-args> ::= I <template-arg>+ E, after the I
+    fn template_args(&mut self) -> Result<Vec<TemplateArg>, DemangleError> {
+	let mut a = Vec::new();
+	while !self.eat(b'E') {
+	    if self.eat(b'L') {
+		let t = self.ty()?;
+		let negative = self.eat(b'n');
+		let v = self.number()? as i128;
+		if !self.eat(b'E') {
+		    return Err(DemangleError::Unexpected(self.pos));
+		}
+		a.push(TemplateArg::Literal(t, if negative { -v } else { v }));
+	    } else {
+		a.push(TemplateArg::Type(self.ty()?));
+	    }
+	}
+	Ok(a)
+    }
+
+    /// <substitution> ::= S_ | S <seq-id> _ | Sa | Sb | Ss | Si | So | Sd
+    fn substitution(&mut self) -> Result<Type, DemangleError> {
+	let start = self.pos;
+	self.pos += 1;
+	if let Some(n) = self.peek().and_then(std_substitution) {
+	    self.pos += 1;
+	    return Ok(Type::Named(n));
+	}
+	let mut i = 0;
+	if !self.eat(b'_') {
+	    let mut n: usize = 0;
+	    loop {
+		let d = match self.next()? {
+		    b'_' => break,
+		    c @ b'0'..=b'9' => c - b'0',
+		    c @ b'A'..=b'Z' => c - b'A' + 10,
+		    _ => return Err(DemangleError::Unexpected(self.pos - 1))
+		};
+		n = n.checked_mul(36)
+		    .and_then(|n| n.checked_add(d as usize))
+		    .ok_or(DemangleError::BadSubstitution(start))?;
+	    }
+	    i = n.checked_add(1).ok_or(DemangleError::BadSubstitution(start))?;
+	}
+	self.subs.get(i).cloned().ok_or(DemangleError::BadSubstitution(start))
+    }
+
+    /// whether a function type, possibly noexcept, is next
+    fn is_function_type(&self) -> bool {
+	self.peek() == Some(b'F') || self.s.get(self.pos..self.pos + 3) == Some(b"DoF")
+    }
+
+    /// <function-type> ::= [<CV-qualifiers>] [Do] F [Y] <type> <bare-function-type> [<ref-qualifier>] E,
+    /// after the qualifiers
+    fn function_type(&mut self, quals: Qualifiers) -> Result<FunctionType, DemangleError> {
+	let is_noexcept = self.eat(b'D');
+	if is_noexcept {
+	    self.pos += 1;
+	}
+	self.pos += 1;
+	let is_extern_c = self.eat(b'Y');
+	let ret = self.ty()?;
+	let mut params = Vec::new();
+	let mut ref_qual = None;
+	while !self.eat(b'E') {
+	    match (self.peek(), self.s.get(self.pos + 1)) {
+		(Some(b'R'), Some(b'E')) => ref_qual = Some(RefQualifier::LValue),
+		(Some(b'O'), Some(b'E')) => ref_qual = Some(RefQualifier::RValue),
+		_ => {
+		    params.push(self.ty()?);
+		    continue;
+		}
+	    }
+	    self.pos += 1;
+	}
+	if params.is_empty() {
+	    return Err(DemangleError::Unexpected(self.pos - 1));
+	}
+	Ok(FunctionType {
+	    ret: Box::new(ret),
+	    params,
+	    is_noexcept,
+	    is_extern_c,
+	    quals,
+	    ref_qual
+	})
+    }
+
+    /// <builtin-type> starting with D, after the D
+    fn extended_builtin(&mut self) -> Result<Type, DemangleError> {
+	let start = self.pos - 1;
+	Ok(match self.next()? {
+	    b'd' => Type::Decimal64,
+	    b'e' => Type::Decimal128,
+	    b'f' => Type::Decimal32,
+	    b'h' => Type::Half,
+	    b'i' => Type::Char32,
+	    b's' => Type::Char16,
+	    b'u' => Type::Char8,
+	    b'a' => Type::Auto,
+	    b'c' => Type::DecltypeAuto,
+	    b'n' => Type::NullPtr,
+	    c @ (b'F' | b'B' | b'U') => {
+		let n = u32::try_from(self.number()?).map_err(|_| DemangleError::Unexpected(start))?;
+		match (c, self.next()?) {
+		    (b'F', b'_') => Type::FloatN(n),
+		    (b'F', b'x') => Type::FloatNx(n),
+		    (b'F', b'b') if n == 16 => Type::BFloat16,
+		    (b'B', b'_') => Type::BitInt(n),
+		    (b'U', b'_') => Type::UBitInt(n),
+		    _ => return Err(DemangleError::Unexpected(self.pos - 1))
+		}
+	    },
+	    _ => return Err(DemangleError::Unexpected(start))
+	})
+    }
+
+    /// <CV-qualifiers> ::= [r] [V] [K]
+    fn qualifiers(&mut self) -> Qualifiers {
+	let mut q = Qualifiers::NONE;
+	q.is_restrict = self.eat(b'r');
+	q.is_volatile = self.eat(b'V');
+	q.is_const = self.eat(b'K');
+	q
+    }
+
+    /// <type>
+    fn ty(&mut self) -> Result<Type, DemangleError> {
+	if self.depth == MAX_DEPTH {
+	    return Err(DemangleError::TooDeep(self.pos));
+	}
+	self.depth += 1;
+	let t = self.nested_ty();
+	self.depth -= 1;
+	t
+    }
+
+    /// <type>, counted by ty
+    fn nested_ty(&mut self) -> Result<Type, DemangleError> {
+	let c = self.peek().ok_or(DemangleError::UnexpectedEnd)?;
+	let t = match c {
+	    b'P' | b'R' | b'O' => {
+		self.pos += 1;
+		let t = Box::new(self.ty()?);
+		match c {
+		    b'P' => Type::Pointer(t),
+		    b'R' => Type::LValueRef(t),
+		    _ => Type::RValueRef(t)
+		}
+	    },
+	    b'r' | b'V' | b'K' => {
+		let q = self.qualifiers();
+		// cv-qualifiers of a function type are part of it
+		if self.is_function_type() {
+		    Type::Function(self.function_type(q)?)
+		} else {
+		    Type::Qualified(q, Box::new(self.ty()?))
+		}
+	    },
+	    b'F' | b'D' if self.is_function_type() => Type::Function(self.function_type(Qualifiers::NONE)?),
+	    b'T' => Type::TemplateParam(self.template_param()?),
+	    b'M' => {
+		self.pos += 1;
+		let class = self.ty()?;
+		Type::MemberPointer(Box::new(class), Box::new(self.ty()?))
+	    },
+	    b'A' => {
+		self.pos += 1;
+		let n = match self.peek() {
+		    Some(b'_') => ArrayBound::Unknown,
+		    Some(b'T') => ArrayBound::TemplateParam(self.template_param()?),
+		    _ => ArrayBound::Value(self.number()? as u64)
+		};
+		if !self.eat(b'_') {
+		    return Err(DemangleError::Unexpected(self.pos));
+		}
+		Type::Array(n, Box::new(self.ty()?))
+	    },
+	    // named types record their own prefixes
+	    b'N' | b'0'..=b'9' => return Ok(Type::Named(self.name(true)?)),
+	    b'S' if self.is_std() => return Ok(Type::Named(self.name(true)?)),
+	    b'S' => {
+		let start = self.pos;
+		let t = self.substitution()?;
+		if self.peek() != Some(b'I') {
+		    return Ok(t);
+		}
+		// substituted template name followed by its arguments
+		let Type::Named(n) = t else {
+		    return Err(DemangleError::BadSubstitution(start));
+		};
+		let mut c = n.components().to_vec();
+		self.component_args(&mut c, true, false)?;
+		return Ok(Type::Named(Scope::from_components(c)));
+	    },
+	    b'D' => {
+		self.pos += 1;
+		return self.extended_builtin();
+	    },
+	    b'u' => {
+		self.pos += 1;
+		Type::Vendor(self.source_name()?)
+	    },
+	    _ => {
+		self.pos += 1;
+		return builtin(c).ok_or(DemangleError::Unexpected(self.pos - 1));
+	    }
+	};
+	self.subs.push(t.clone());
+	Ok(t)
+    }
+}
+
+/// <builtin-type>
+fn builtin(c: u8) -> Option<Type> {
+    Some(match c {
+	b'a' => Type::SChar,
+	b'b' => Type::Bool,
+	b'c' => Type::Char,
+	b'd' => Type::Double,
+	b'e' => Type::LongDouble,
+	b'f' => Type::Float,
+	b'g' => Type::Float128,
+	b'h' => Type::UChar,
+	b'i' => Type::Int,
+	b'j' => Type::UInt,
+	b'l' => Type::Long,
+	b'm' => Type::ULong,
+	b'n' => Type::Int128,
+	b'o' => Type::UInt128,
+	b's' => Type::Short,
+	b't' => Type::UShort,
+	b'v' => Type::Void,
+	b'w' => Type::WChar,
+	b'x' => Type::LLong,
+	b'y' => Type::ULLong,
+	b'z' => Type::Ellipsis,
+	_ => return None
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn demangle_round_trip() {
+	use super::demangle;
+	use crate::{
+	    Type,
+	    Func,
+	    Scope,
+	    TemplateArg,
+	    CtorKind,
+	    DtorKind,
+	    Qualifiers,
+	    RefQualifier
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let widget = || Type::named(String::from("gfx::Widget"));
+	let boxed = |t| Type::Named(Scope::new(String::from("Box")).with_template_args(vec![TemplateArg::Type(t)]));
+	let funcs = [
+	    Func::new(String::from("func"), vec![Type::Void]),
+	    Func::new(String::from("myNamespace::inner::func"), vec![Type::Int, Type::Double, Type::Ellipsis]),
+	    Func::new(String::from("f"), vec![Type::pointer(Type::constant(Type::Char)), Type::pointer(Type::constant(Type::Char))]),
+	    Func::new(String::from("gfx::draw"), vec![Type::lvalue_ref(Type::constant(widget())), Type::pointer(widget())]),
+	    Func::new(String::from("Foo::bar"), vec![Type::pointer(Type::named(String::from("Foo"))), Type::rvalue_ref(Type::ULLong)]),
+	    Func::new(String::from("use"), vec![boxed(boxed(Type::Int)), boxed(Type::Int)]),
+	    Func::new(String::from("m::f"), vec![Type::pointer(Type::Int)]).with_template_args(vec![TemplateArg::Type(boxed(Type::Int))]),
+	    Func::new(String::from("lit"), vec![Type::Void]).with_template_args(vec![TemplateArg::Literal(Type::Long, -3)]).with_return(Type::Int),
+	    Func::constructor(String::from("ns::Foo"), CtorKind::Allocating, vec![Type::named(String::from("ns::Foo"))]),
+	    Func::destructor(String::from("Foo"), DtorKind::Complete),
+	    Func::constructor(String::from("Foo"), CtorKind::Base, vec![Type::pointer(Type::Float)]).with_template_args(vec![TemplateArg::Type(Type::Float)]),
+	    Func::new(String::from("Vec3::operator+"), vec![Type::lvalue_ref(Type::constant(Type::named(String::from("Vec3"))))]),
+	    Func::new(String::from("operator-"), vec![Type::named(String::from("Vec3"))]),
+	    Func::new(String::from("operator new[]"), vec![Type::ULong]),
+	    Func::new(String::from("operator\"\" _km"), vec![Type::ULLong]),
+	    Func::conversion(String::from("Vec3"), Type::pointer(Type::named(String::from("Vec3")))),
+	    Func::new(String::from("Foo::get"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST | Qualifiers::VOLATILE).with_ref_qualifier(RefQualifier::RValue),
+	    Func::new(String::from("f"), vec![Type::Bool, Type::LongDouble, Type::Char8, Type::Char16, Type::Char32, Type::NullPtr, Type::Auto, Type::DecltypeAuto]),
+	    Func::new(String::from("g"), vec![Type::Half, Type::FloatN(16), Type::FloatNx(32), Type::BFloat16, Type::BitInt(8), Type::UBitInt(128)]),
+	    Func::new(String::from("h"), vec![Type::Decimal32, Type::Decimal64, Type::Decimal128, Type::Vendor(String::from("foo")), Type::pointer(Type::Vendor(String::from("foo")))]),
+	    Func::new(String::from("b"), vec![Type::Void]).with_template_args(vec![TemplateArg::Literal(Type::Bool, 0)]),
+	    Func::new(String::from("(anonymous namespace)::helper"), vec![Type::Void]),
+	    Func::new(String::from("s"), vec![Type::Int]).with_internal_linkage(),
+	    Func::new(String::from("n::h"), vec![Type::Void]).with_internal_linkage()
+	];
+	for f in funcs {
+	    assert_eq!(demangle(&f.mangle()), Ok(f));
+	}
+	for s in ["_ZSt4sortv", "_Z1bRSsPKSs", "_Z1dSt6vectorIiSaIiEEPS1_", "_ZNSt6vectorIiSaIiEE9push_backEOi", "_ZNKSs4sizeEv",
+	    "_Z1fB5cxx11v", "_Z2f8St6vectorI1AB1xSaIS0_EE", "_ZN1S4nameB5cxx11Ev",
+	    "_ZNKSt3__112basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEE4sizeEv",
+	    "_Z1dPFvPFviEES0_", "_Z1ePPFicEPKS0_", "_Z1hPFvvEPDoFvvE", "_Z1gIKFviREEvv", "_Z1gIFviOEEvv", "_Z1cPFYviE",
+	    "_Z1aRA16_i", "_Z1bPA3_A4_iS1_", "_Z1cRA5_Kc", "_Z1dRA_i", "_Z1eRA2_PFviE", "_Z1gIA2_iEvv",
+	    "_Z1cM6WidgetKFviREMS_FviOE", "_Z1dM6WidgetiPS_MS_Ki", "_Z1eM6WidgetFvPS_EPMS_FivE", "_Z1fM6WidgetDoFvvE",
+	    "_Z1fIiEvPT_", "_Z3maxIiET_S0_S0_", "_Z4convIidET0_T_RKS0_PS0_", "_Z3putIcEv3BoxIT_ES1_", "_Z1gIlEvPFvT_EM1SS0_", "_ZN1ScvPT_IiEEv", "_Z2faILi4EEvRAT__i"] {
+	    assert_eq!(&demangle(s).unwrap().mangle(), s);
+	}
+    }
+
+    #[test]
+    fn demangle_display() {
+	use super::demangle;
+	use alloc::string::ToString;
+	assert_eq!(demangle("_Z4funcv").unwrap().to_string(), "func()");
+	assert_eq!(demangle("_ZN3gfx4drawERKNS_6WidgetEPS0_").unwrap().to_string(), "gfx::draw(gfx::Widget const&, gfx::Widget*)");
+	assert_eq!(demangle("_Z1kPrVKPi").unwrap().to_string(), "k(int* const volatile restrict*)");
+	assert_eq!(demangle("_Z3useN2ns3VecIfLi4EEE3BoxIS2_IiEES3_").unwrap().to_string(), "use(ns::Vec<float, 4>, Box<Box<int> >, Box<int>)");
+	assert_eq!(demangle("_ZN1m1fIiEEvPi").unwrap().to_string(), "void m::f<int>(int*)");
+	assert_eq!(demangle("_ZN2ns3BarIiEC1Ev").unwrap().to_string(), "ns::Bar<int>::Bar()");
+	assert_eq!(demangle("_ZN3FooD0Ev").unwrap().to_string(), "Foo::~Foo()");
+	assert_eq!(demangle("_ZN4Vec3plERKS_").unwrap().to_string(), "Vec3::operator+(Vec3 const&)");
+	assert_eq!(demangle("_ZN4Vec3cvPS_Ev").unwrap().to_string(), "Vec3::operator Vec3*()");
+	assert_eq!(demangle("_Znwmi").unwrap().to_string(), "operator new(unsigned long, int)");
+	assert_eq!(demangle("_ZNVKO3Foo2cvEv").unwrap().to_string(), "Foo::cv() const volatile &&");
+	assert_eq!(demangle("_ZNK3Foo1tIiEEiv").unwrap().to_string(), "int Foo::t<int>() const");
+	assert_eq!(demangle("_Z1fIbLb1EEvv").unwrap().to_string(), "void f<bool, true>()");
+	assert_eq!(demangle("_Z1fDF16_DB8_DU8_Dn").unwrap().to_string(), "f(_Float16, _BitInt(8), unsigned _BitInt(8), decltype(nullptr))");
+	assert_eq!(demangle("_Zli3_kmy").unwrap().to_string(), "operator\"\" _km(unsigned long long)");
+	assert_eq!(demangle("_ZN12_GLOBAL__N_11x1gEi").unwrap().to_string(), "(anonymous namespace)::x::g(int)");
+	assert_eq!(demangle("_ZL1si").unwrap().to_string(), "s(int)");
+	assert_eq!(demangle("_Z1aSaIcEPS_").unwrap().to_string(), "a(std::allocator<char>, std::allocator<char>*)");
+	assert_eq!(demangle("_Z1cRSiRSoRSd").unwrap().to_string(), "c(std::basic_istream<char, std::char_traits<char> >&, std::basic_ostream<char, std::char_traits<char> >&, std::basic_iostream<char, std::char_traits<char> >&)");
+	assert_eq!(demangle("_ZN1TB1xC1Ev").unwrap().to_string(), "T[abi:x]::T()");
+	assert_eq!(demangle("_Z1kB1tB1xv").unwrap().to_string(), "k[abi:t][abi:x]()");
+	assert_eq!(demangle("_Z1dPFvPFviEES0_").unwrap().to_string(), "d(void (*)(void (*)(int)), void (*)(int))");
+	assert_eq!(demangle("_Z1fPFvizE").unwrap().to_string(), "f(void (*)(int, ...))");
+	assert_eq!(demangle("_Z1fPA2_iS0_").unwrap().to_string(), "f(int (*) [2], int (*) [2])");
+	assert_eq!(demangle("_Z1gIA2_iEvv").unwrap().to_string(), "void g<int [2]>()");
+	assert_eq!(demangle("_Z1cM6WidgetKFviREMS_FviOE").unwrap().to_string(), "c(void (Widget::*)(int) const &, void (Widget::*)(int) &&)");
+	assert_eq!(demangle("_Z3maxIiET_S0_S0_").unwrap().to_string(), "int max<int>(int, int)");
+	assert_eq!(demangle("_Z4convIidET0_T_RKS0_PS0_").unwrap().to_string(), "double conv<int, double>(int, double const&, double*)");
+	assert_eq!(demangle("_Z2frIRiEvOT_").unwrap().to_string(), "void fr<int&>(int&)");
+	assert_eq!(demangle("_Z2faILi4EEvRAT__i").unwrap().to_string(), "void fa<4>(int (&) [4])");
+	// clone suffixes from nm output name the same function
+	assert_eq!(demangle("_Z3foov.cold").unwrap().to_string(), "foo()");
+	assert_eq!(demangle("_ZN2ns4funcEi.isra.0").unwrap().to_string(), "ns::func(int)");
+	assert_eq!(demangle("_Z3fooi.constprop.0.isra.0").unwrap().to_string(), "foo(int)");
+	assert_eq!(demangle("_ZN1ScvPT_IiEEv").unwrap().to_string(), "S::operator int*<int>()");
+	assert_eq!(demangle("_ZNKSs4sizeEv").unwrap().to_string(), "std::basic_string<char, std::char_traits<char>, std::allocator<char> >::size() const");
+    }
+
+    #[test]
+    fn demangle_errors() {
+	use super::{
+	    demangle,
+	    DemangleError
+	};
+	assert_eq!(demangle("main"), Err(DemangleError::NotMangled));
+	assert_eq!(demangle("_Z4func"), Err(DemangleError::UnexpectedEnd));
+	assert_eq!(demangle("_Z4funcS_"), Err(DemangleError::BadSubstitution(7)));
+	assert_eq!(demangle("_Z4funcQ"), Err(DemangleError::Unexpected(7)));
+	assert_eq!(demangle("_Z0v"), Err(DemangleError::Unexpected(2)));
+	assert_eq!(demangle("_Z01fv"), Err(DemangleError::Unexpected(2)));
+	assert_eq!(demangle("_Z3foov."), Err(DemangleError::UnexpectedEnd));
+	assert_eq!(demangle("_Z3foov.a+"), Err(DemangleError::Unexpected(9)));
+    }
+
+    #[test]
+    fn demangle_depth() {
+	use super::{
+	    demangle,
+	    DemangleError,
+	    MAX_DEPTH
+	};
+	use alloc::string::String;
+	let nested = |n| {
+	    let mut s = String::from("_Z1f");
+	    s.extend(core::iter::repeat_n('P', n));
+	    s.push('i');
+	    s
+	};
+	assert!(demangle(&nested(MAX_DEPTH - 1)).is_ok());
+	assert_eq!(demangle(&nested(MAX_DEPTH)), Err(DemangleError::TooDeep(4 + MAX_DEPTH)));
+	assert_eq!(demangle(&nested(200000)), Err(DemangleError::TooDeep(4 + MAX_DEPTH)));
+	// template arguments nest through names
+	let templates = |n| {
+	    let mut s = String::from("_Z1f");
+	    for _ in 0..n {
+		s.push_str("1AI");
+	    }
+	    s.push('i');
+	    s.extend(core::iter::repeat_n('E', n));
+	    s
+	};
+	assert!(demangle(&templates(MAX_DEPTH - 1)).is_ok());
+	assert!(matches!(demangle(&templates(MAX_DEPTH)), Err(DemangleError::TooDeep(_))));
+    }
+}
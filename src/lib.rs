@@ -37,33 +37,61 @@ OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //!
 //! Mangle names of functions for the ability to have a C++ ABI whilst allowing for overloading
 //!
-//! Limitations
-//! 
-//! - only supports types that Rust supports
+//! Variables at namespace scope and static data members are mangled with Var
+//!
+//! Vtables, typeinfo objects, thunks, guard variables and thread_local wrappers are mangled with SpecialName
+//!
+//! Symbols can be parsed back into a Func with demangle
 //!
-//! - does not support pointers
+//! Names in std are mangled for libstdc++ unless StdLib selects libc++, globally or for a Func
 //!
-//! - does not check for validity
+//! Limitations
+//! 
+//! - Func::new does not check for validity, use Func::try_new to reject names and parameters that cannot be mangled
 
 extern crate alloc;
 
+mod demangle;
+mod operator;
+mod special;
+
+pub use demangle::{
+    demangle,
+    DemangleError
+};
+pub use operator::Operator;
+pub use special::{
+    CallOffset,
+    SpecialName
+};
+
 use core::{
     write,
-    fmt::Write,
+    fmt::{self, Display, Formatter, Write},
 };
 use alloc::string::String;
 use alloc::vec::Vec;
+use alloc::vec;
+use alloc::format;
+use alloc::boxed::Box;
+use alloc::borrow::Cow;
+use core::ops::BitOr;
+use core::sync::atomic::{AtomicU8, Ordering};
 
 /// Non exhaustive set of parameter types
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 #[non_exhaustive]
 pub enum Type {
     /// char
     Char, // c
     /// signed char
     SChar, // a
+    /// bool
+    Bool, // b
     /// double
     Double, // d
+    /// long double, __float80
+    LongDouble, // e
     /// float
     Float, // f
     /// __float128
@@ -74,7 +102,8 @@ pub enum Type {
     Int, // i
     /// unsigned int
     UInt, // j
-    //Const, // K
+    /// cv-qualified inner type
+    Qualified(Qualifiers, Box<Type>), // r V K
     /// long
     Long, // l
     /// unsigned long
@@ -83,14 +112,18 @@ pub enum Type {
     Int128, // n
     /// unsigned __int128
     UInt128, // o
-    //Pointer, // p,
+    /// T* (pointer to inner type)
+    Pointer(Box<Type>), // P
+    /// T& (lvalue reference to inner type)
+    LValueRef(Box<Type>), // R
+    /// T&& (rvalue reference to inner type)
+    RValueRef(Box<Type>), // O
     /// short
     Short, // s
     /// unsinged short
     UShort, // t
     /// void (no parameters)
     Void, // v,
-    //Volatile, // V
     /// wchar_t
     WChar, // w,
     /// long long
@@ -98,129 +131,2468 @@ pub enum Type {
     /// unsigned long logn
     ULLong, // y
     /// ... (variadic)
-    Ellipsis // z
+    Ellipsis, // z
+    /// IEEE 754r 64 bit decimal floating point
+    Decimal64, // Dd
+    /// IEEE 754r 128 bit decimal floating point
+    Decimal128, // De
+    /// IEEE 754r 32 bit decimal floating point
+    Decimal32, // Df
+    /// half (16 bit IEEE 754r floating point)
+    Half, // Dh
+    /// _FloatN
+    FloatN(u32), // DF <N> _
+    /// _FloatNx
+    FloatNx(u32), // DF <N> x
+    /// std::bfloat16_t
+    BFloat16, // DF16b
+    /// _BitInt(N)
+    BitInt(u32), // DB <N> _
+    /// unsigned _BitInt(N)
+    UBitInt(u32), // DU <N> _
+    /// char32_t
+    Char32, // Di
+    /// char16_t
+    Char16, // Ds
+    /// char8_t
+    Char8, // Du
+    /// auto
+    Auto, // Da
+    /// decltype(auto)
+    DecltypeAuto, // Dc
+    /// std::nullptr_t
+    NullPtr, // Dn
+    /// vendor extended type
+    Vendor(String), // u <source-name>
+    /// class, struct, union or enum type
+    Named(Scope), // <source-name> or N ... E
+    /// function type, usually pointed to as in void (*)(int)
+    Function(FunctionType), // F ... E
+    /// array with the bound
+    Array(ArrayBound, Box<Type>), // A [<bound>] _ <type>
+    /// pointer to member of the class, a member function if the member is a function type
+    MemberPointer(Box<Type>, Box<Type>), // M <class type> <member type>
+    /// the template parameter with the index, counting from 0, of the function template
+    /// whose signature it appears in
+    TemplateParam(u32) // T_ or T <index - 1> _
 }
 
-#[derive(Clone, PartialEq, Hash, Debug)]
-#[non_exhaustive]
-enum Scope {
-    Unscoped(String),
-    Nested(Vec<String>), //N ... E,
+impl Type {
+    /// pointer to t
+    pub fn pointer(t: Type) -> Self {
+	Self::Pointer(Box::new(t))
+    }
+
+    /// lvalue reference to t
+    pub fn lvalue_ref(t: Type) -> Self {
+	Self::LValueRef(Box::new(t))
+    }
+
+    /// rvalue reference to t
+    pub fn rvalue_ref(t: Type) -> Self {
+	Self::RValueRef(Box::new(t))
+    }
+
+    /// array of n t
+    pub fn array(n: u64, t: Type) -> Self {
+	Self::Array(ArrayBound::Value(n), Box::new(t))
+    }
+
+    /// array of t of unknown bound
+    pub fn unbounded_array(t: Type) -> Self {
+	Self::Array(ArrayBound::Unknown, Box::new(t))
+    }
+
+    /// pointer to member of class, as in int Widget::* or void (Widget::*)(int)
+    pub fn member_pointer(class: Type, member: Type) -> Self {
+	Self::MemberPointer(Box::new(class), Box::new(member))
+    }
+
+    /// class, struct, union or enum type with the full C++ name n
+    pub fn named(n: String) -> Self {
+	Self::Named(Scope::new(n))
+    }
+
+    /// t with the qualifiers q added, merging with any qualifiers t already has
+    pub fn qualified(q: Qualifiers, t: Type) -> Self {
+	match t {
+	    Self::Qualified(inner, t) => Self::Qualified(q | inner, t),
+	    t => Self::Qualified(q, Box::new(t))
+	}
+    }
+
+    /// const t
+    pub fn constant(t: Type) -> Self {
+	Self::qualified(Qualifiers::CONST, t)
+    }
+
+    /// volatile t
+    pub fn volatile(t: Type) -> Self {
+	Self::qualified(Qualifiers::VOLATILE, t)
+    }
+
+    /// t __restrict
+    pub fn restrict(t: Type) -> Self {
+	Self::qualified(Qualifiers::RESTRICT, t)
+    }
+
+    /// check named types and that ... is not nested inside another type
+    fn validate(&self) -> Result<(), MangleError> {
+	match self {
+	    Type::Pointer(t) | Type::LValueRef(t) | Type::RValueRef(t) | Type::Qualified(_, t) | Type::Array(_, t) => match **t {
+		Type::Ellipsis => Err(MangleError::MisplacedEllipsis),
+		_ => t.validate()
+	    },
+	    Type::Named(n) => n.validate(),
+	    Type::Function(t) => t.validate(),
+	    Type::MemberPointer(c, t) => {
+		c.validate()?;
+		t.validate()
+	    },
+	    Type::Vendor(n) if !is_identifier(n) => Err(MangleError::InvalidIdentifier(n.clone())),
+	    _ => Ok(())
+	}
+    }
+
+    /// type with top level cv-qualifiers removed, as they are not part of a function signature
+    fn unqualified(&self) -> &Type {
+	match self {
+	    Self::Qualified(_, t) => t,
+	    t => t
+	}
+    }
+
+    /// type of a parameter declared with the type, without top level cv-qualifiers and
+    /// with arrays and functions adjusted to pointers
+    fn adjusted(&self) -> Cow<'_, Type> {
+	match self.unqualified() {
+	    Type::Array(_, t) => Cow::Owned(Type::Pointer(t.clone())),
+	    t @ Type::Function(_) => Cow::Owned(Type::pointer(t.clone())),
+	    t => Cow::Borrowed(t)
+	}
+    }
+
+    /// whether the type is recorded in the substitution table here, builtin types other
+    /// than vendor extensions are never candidates and named types are recorded by their Scope
+    fn is_candidate(&self) -> bool {
+	matches!(self, Type::Pointer(_) | Type::LValueRef(_) | Type::RValueRef(_) | Type::Qualified(..) | Type::Vendor(_) | Type::Function(_) | Type::Array(..) | Type::MemberPointer(..) | Type::TemplateParam(_))
+    }
+
+    /// ABI tags of named types within the type, which a function returning it inherits
+    fn abi_tags(&self, tags: &mut Vec<String>) {
+	match self {
+	    Type::Qualified(_, t) | Type::Pointer(t) | Type::LValueRef(t) | Type::RValueRef(t) | Type::Array(_, t) => t.abi_tags(tags),
+	    Type::Named(n) => n.abi_tags(tags),
+	    Type::MemberPointer(c, t) => {
+		c.abi_tags(tags);
+		t.abi_tags(tags);
+	    },
+	    Type::Function(t) => {
+		t.ret.abi_tags(tags);
+		for i in &t.params {
+		    i.abi_tags(tags);
+		}
+	    },
+	    _ => ()
+	}
+    }
+
+    /// type with the template parameters it refers to replaced by the type arguments args
+    fn resolve(&self, args: &[TemplateArg]) -> Result<Type, MangleError> {
+	Ok(match self {
+	    Type::TemplateParam(n) => match args.get(*n as usize) {
+		Some(TemplateArg::Type(t)) => t.clone(),
+		_ => return Err(MangleError::InvalidTemplateParam(*n))
+	    },
+	    Type::Pointer(t) => Type::pointer(t.resolve(args)?),
+	    // references to references collapse, to && only if both are &&
+	    Type::LValueRef(t) => match t.resolve(args)? {
+		Type::LValueRef(t) | Type::RValueRef(t) => Type::LValueRef(t),
+		t => Type::lvalue_ref(t)
+	    },
+	    Type::RValueRef(t) => match t.resolve(args)? {
+		t @ (Type::LValueRef(_) | Type::RValueRef(_)) => t,
+		t => Type::rvalue_ref(t)
+	    },
+	    Type::Qualified(q, t) => Type::qualified(*q, t.resolve(args)?),
+	    Type::Array(n, t) => Type::Array(n.resolve(args)?, Box::new(t.resolve(args)?)),
+	    Type::MemberPointer(c, t) => Type::member_pointer(c.resolve(args)?, t.resolve(args)?),
+	    Type::Function(t) => Type::Function(FunctionType {
+		ret: Box::new(t.ret.resolve(args)?),
+		params: t.params.iter().map(|p| p.resolve(args)).collect::<Result<_, _>>()?,
+		..t.clone()
+	    }),
+	    Type::Named(n) => Type::Named(n.resolve(args)?),
+	    t => t.clone()
+	})
+    }
+
+    /// mangling without substitutions, identifying the type in the substitution table
+    fn key(&self, std_lib: StdLib) -> String {
+	let mut m = Mangler::plain(std_lib);
+	self.mangle_inner(&mut m);
+	m.s
+    }
+
+    fn mangle(&self, m: &mut Mangler) {
+	if !self.is_candidate() || !m.compress {
+	    return self.mangle_inner(m);
+	}
+	let key = self.key(m.std_lib);
+	if !m.substitute(&key) {
+	    self.mangle_inner(m);
+	    m.add(key);
+	}
+    }
+
+    fn mangle_inner(&self, m: &mut Mangler) {
+	let s = &mut m.s;
+	match self {
+	    Type::Pointer(t) => {
+		s.push('P');
+		t.mangle(m);
+	    },
+	    Type::LValueRef(t) => {
+		s.push('R');
+		t.mangle(m);
+	    },
+	    Type::RValueRef(t) => {
+		s.push('O');
+		t.mangle(m);
+	    },
+	    Type::SChar => s.push('a'),
+	    Type::Bool => s.push('b'),
+	    Type::Double => s.push('d'),
+	    Type::LongDouble => s.push('e'),
+	    Type::Float => s.push('f'),
+	    Type::Float128 => s.push('g'),
+	    Type::UChar => s.push('h'),
+	    Type::Int => s.push('i'),
+	    Type::UInt => s.push('j'),
+	    Type::Qualified(q, t) => {
+		q.mangle(s);
+		t.mangle(m);
+	    },
+	    Type::Long => s.push('l'),
+	    Type::ULong => s.push('m'),
+	    Type::Int128 => s.push('n'),
+	    Type::UInt128 => s.push('o'),
+	    Type::Short => s.push('s'),
+	    Type::UShort => s.push('t'),
+	    Type::Void => s.push('v'),
+	    Type::WChar => s.push('w'),
+	    Type::LLong => s.push('x'),
+	    Type::ULLong => s.push('y'),
+	    Type::Ellipsis => s.push('z'),
+	    Type::Decimal64 => s.push_str("Dd"),
+	    Type::Decimal128 => s.push_str("De"),
+	    Type::Decimal32 => s.push_str("Df"),
+	    Type::Half => s.push_str("Dh"),
+	    Type::FloatN(n) => {
+		let _ = write!(s, "DF{}_", n);
+	    },
+	    Type::FloatNx(n) => {
+		let _ = write!(s, "DF{}x", n);
+	    },
+	    Type::BFloat16 => s.push_str("DF16b"),
+	    Type::BitInt(n) => {
+		let _ = write!(s, "DB{}_", n);
+	    },
+	    Type::UBitInt(n) => {
+		let _ = write!(s, "DU{}_", n);
+	    },
+	    Type::Char32 => s.push_str("Di"),
+	    Type::Char16 => s.push_str("Ds"),
+	    Type::Char8 => s.push_str("Du"),
+	    Type::Auto => s.push_str("Da"),
+	    Type::DecltypeAuto => s.push_str("Dc"),
+	    Type::NullPtr => s.push_str("Dn"),
+	    Type::Vendor(n) => {
+		s.push('u');
+		source_name(s, n);
+	    },
+	    Type::Named(n) => n.mangle(m, true),
+	    Type::Function(t) => t.mangle(m),
+	    Type::TemplateParam(n) => {
+		s.push('T');
+		if let Some(n) = n.checked_sub(1) {
+		    let _ = write!(s, "{}", n);
+		}
+		s.push('_');
+	    },
+	    Type::MemberPointer(c, t) => {
+		s.push('M');
+		c.mangle(m);
+		t.mangle(m);
+	    },
+	    Type::Array(n, t) => {
+		s.push('A');
+		n.mangle(s);
+		s.push('_');
+		t.mangle(m);
+	    },
+	    Type::Char => s.push('c')
+	}
+    }
 }
 
-impl Scope {
-    fn new(n: String) -> Self {
-	if n.contains("::") {
-	    let mut v = Vec::new();
-	    n.split("::").collect::<Vec<&str>>().iter().for_each(|e| v.push(String::from(*e)));
-	    Self::Nested(v)
-	} else {
-	    Self::Unscoped(n)
+/// cv-qualifiers of a type
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Qualifiers {
+    /// const
+    pub is_const: bool, // K
+    /// volatile
+    pub is_volatile: bool, // V
+    /// __restrict
+    pub is_restrict: bool // r
+}
+
+impl Qualifiers {
+    /// no qualifiers
+    pub const NONE: Self = Self { is_const: false, is_volatile: false, is_restrict: false };
+    /// const
+    pub const CONST: Self = Self { is_const: true, ..Self::NONE };
+    /// volatile
+    pub const VOLATILE: Self = Self { is_volatile: true, ..Self::NONE };
+    /// __restrict
+    pub const RESTRICT: Self = Self { is_restrict: true, ..Self::NONE };
+
+    /// whether no qualifier is set
+    pub fn is_empty(&self) -> bool {
+	*self == Self::NONE
+    }
+
+    /// ABI requires the order r V K
+    fn mangle(&self, s: &mut String) {
+	if self.is_restrict {
+	    s.push('r');
+	}
+	if self.is_volatile {
+	    s.push('V');
+	}
+	if self.is_const {
+	    s.push('K');
 	}
     }
+}
 
-    fn mangle(&self, t: &[Type]) -> String {
-	let mut s = String::from("_Z");
+impl Type {
+    /// write type followed by declarator d, which is put in parentheses before the
+    /// parameters of a function type, as in void (*)(int)
+    fn fmt_declarator(&self, f: &mut Formatter<'_>, d: &str) -> fmt::Result {
 	match self {
-	    Scope::Unscoped(st) => {
-		let mut l = String::new();
-		let _ = write!(l, "{}", st.len());
-		s.push_str(&l);
-		s.push_str(st);
-	    },
-	    Scope::Nested(b) => {
-		s.push('N');
-		for i in b {
-		    let mut l = String::new();
-		    let _ = write!(l, "{}", i.len());
-		    s.push_str(&l);
-		    s.push_str(i);
+	    Type::Pointer(t) => t.fmt_declarator(f, &format!("*{}", d)),
+	    Type::LValueRef(t) => t.fmt_declarator(f, &format!("&{}", d)),
+	    Type::RValueRef(t) => t.fmt_declarator(f, &format!("&&{}", d)),
+	    Type::Qualified(q, t) => t.fmt_declarator(f, &format!(" {}{}", q, d)),
+	    Type::MemberPointer(c, t) => t.fmt_declarator(f, &format!(" {}::*{}", c, d)),
+	    Type::Function(t) => {
+		let mut s = String::from(" ");
+		if !d.is_empty() {
+		    write!(s, "({})", d.trim_start())?;
+		}
+		fmt_params(&mut s, &t.params)?;
+		if !t.quals.is_empty() {
+		    write!(s, " {}", t.quals)?;
+		}
+		if let Some(r) = t.ref_qual {
+		    write!(s, " {}", r)?;
+		}
+		if t.is_noexcept {
+		    s.push_str(" noexcept");
 		}
-		s.push('E');
-	    }
-	}
-	for i in t {
-	    s.push(match *i {
-		Type::SChar => 'a',
-		Type::Double => 'd',
-		Type::Float => 'f',
-		Type::Float128 => 'g',
-		Type::UChar => 'h',
-		Type::Int => 'i',
-		Type::UInt => 'j',
-		//Type::Const => 'K',
-		Type::Long => 'l',
-		Type::ULong => 'm',
-		Type::Int128 => 'n',
-		Type::UInt128 => 'o',
-		//Type::Pointer => 'p',
-		Type::Short => 's',
-		Type::UShort => 't',
-		Type::Void => 'v',
-		//Type::Volatile => 'V',
-		Type::WChar => 'w',
-		Type::LLong => 'x',
-		Type::ULLong => 'y',
-		Type::Ellipsis => 'z',
-		Type::Char => 'c'
-	    });
-	}
-	s
+		t.ret.fmt_declarator(f, &s)
+	    },
+	    Type::Array(n, t) => {
+		let mut s = match d {
+		    "" => String::from(" "),
+		    // further bounds of a multidimensional array
+		    d if d.ends_with(']') => String::from(d),
+		    d => format!(" ({}) ", d.trim_start())
+		};
+		write!(s, "[{}]", n)?;
+		t.fmt_declarator(f, &s)
+	    },
+	    _ => write!(f, "{}{}", self, d)
+	}
     }
 }
 
-/// Function to mangle
-pub struct Func {
-    scope: Scope,
-    params: Vec<Type>
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    Type::Pointer(_) | Type::LValueRef(_) | Type::RValueRef(_) | Type::Qualified(..) | Type::Function(_) | Type::Array(..) | Type::MemberPointer(..) => {
+		self.fmt_declarator(f, "")
+	    },
+	    Type::Named(n) => write!(f, "{}", n),
+	    Type::Char => f.write_str("char"),
+	    Type::SChar => f.write_str("signed char"),
+	    Type::Bool => f.write_str("bool"),
+	    Type::Double => f.write_str("double"),
+	    Type::LongDouble => f.write_str("long double"),
+	    Type::Float => f.write_str("float"),
+	    Type::Float128 => f.write_str("__float128"),
+	    Type::UChar => f.write_str("unsigned char"),
+	    Type::Int => f.write_str("int"),
+	    Type::UInt => f.write_str("unsigned int"),
+	    Type::Long => f.write_str("long"),
+	    Type::ULong => f.write_str("unsigned long"),
+	    Type::Int128 => f.write_str("__int128"),
+	    Type::UInt128 => f.write_str("unsigned __int128"),
+	    Type::Short => f.write_str("short"),
+	    Type::UShort => f.write_str("unsigned short"),
+	    Type::Void => f.write_str("void"),
+	    Type::WChar => f.write_str("wchar_t"),
+	    Type::LLong => f.write_str("long long"),
+	    Type::ULLong => f.write_str("unsigned long long"),
+	    Type::Ellipsis => f.write_str("..."),
+	    Type::Decimal64 => f.write_str("decimal64"),
+	    Type::Decimal128 => f.write_str("decimal128"),
+	    Type::Decimal32 => f.write_str("decimal32"),
+	    Type::Half => f.write_str("half"),
+	    Type::FloatN(n) => write!(f, "_Float{}", n),
+	    Type::FloatNx(n) => write!(f, "_Float{}x", n),
+	    Type::BFloat16 => f.write_str("std::bfloat16_t"),
+	    Type::BitInt(n) => write!(f, "_BitInt({})", n),
+	    Type::UBitInt(n) => write!(f, "unsigned _BitInt({})", n),
+	    Type::Char32 => f.write_str("char32_t"),
+	    Type::Char16 => f.write_str("char16_t"),
+	    Type::Char8 => f.write_str("char8_t"),
+	    Type::Auto => f.write_str("auto"),
+	    Type::DecltypeAuto => f.write_str("decltype(auto)"),
+	    Type::NullPtr => f.write_str("decltype(nullptr)"),
+	    Type::TemplateParam(n) => write!(f, "T{}", n),
+	    Type::Vendor(n) => f.write_str(n)
+	}
+    }
 }
 
-impl Func {
-    /// create function to mangle
-    ///
-    /// name - full C++ name of function
-    ///
-    /// params - parameters of function
-    pub fn new(name: String, params: Vec<Type>) -> Self {
+/// space separated, as in int const volatile
+impl Display for Qualifiers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	let q = [(self.is_const, "const"), (self.is_volatile, "volatile"), (self.is_restrict, "restrict")];
+	for (n, (_, name)) in q.iter().filter(|q| q.0).enumerate() {
+	    if n > 0 {
+		f.write_str(" ")?;
+	    }
+	    f.write_str(name)?;
+	}
+	Ok(())
+    }
+}
+
+impl BitOr for Qualifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
 	Self {
-	    scope: Scope::new(name),
-	    params
+	    is_const: self.is_const || rhs.is_const,
+	    is_volatile: self.is_volatile || rhs.is_volatile,
+	    is_restrict: self.is_restrict || rhs.is_restrict
 	}
     }
+}
 
-    /// Mangle function according to Itanium C++ ABI
-    pub fn mangle(&self) -> String {
-	self.scope.mangle(self.params.as_slice())
+/// Bound of an array type
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ArrayBound {
+    /// unknown bound, as in int []
+    Unknown, // empty
+    /// bound with the value
+    Value(u64), // <number>
+    /// bound given by the non-type template parameter with the index, as in int [N]
+    TemplateParam(u32) // T_ or T <index - 1> _
+}
+
+impl ArrayBound {
+    /// bound with the template parameter it refers to replaced by its literal argument in args
+    fn resolve(&self, args: &[TemplateArg]) -> Result<ArrayBound, MangleError> {
+	match self {
+	    ArrayBound::TemplateParam(n) => match args.get(*n as usize) {
+		Some(TemplateArg::Literal(_, v)) => u64::try_from(*v).map(ArrayBound::Value).map_err(|_| MangleError::InvalidTemplateParam(*n)),
+		_ => Err(MangleError::InvalidTemplateParam(*n))
+	    },
+	    n => Ok(*n)
+	}
+    }
+
+    fn mangle(&self, s: &mut String) {
+	match self {
+	    ArrayBound::Unknown => (),
+	    ArrayBound::Value(n) => {
+		let _ = write!(s, "{}", n);
+	    },
+	    ArrayBound::TemplateParam(n) => {
+		s.push('T');
+		if let Some(n) = n.checked_sub(1) {
+		    let _ = write!(s, "{}", n);
+		}
+		s.push('_');
+	    }
+	}
     }
 }
 
-#[cfg(test)]
-mod test {
-    #[test]
-    fn mangle_unscoped() {
-	use super::{
-	    Type,
-	    Func
-	};
-	use alloc::string::String;
-	use alloc::vec;
-	let f1 = Func::new(String::from("func"), vec![Type::Void]);
-	let f2 = Func::new(String::from("func"), vec![Type::Int]);
-	assert_eq!(&f1.mangle(), "_Z4funcv");
-	assert_eq!(&f2.mangle(), "_Z4funci");
+/// empty if unknown
+impl Display for ArrayBound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    ArrayBound::Unknown => Ok(()),
+	    ArrayBound::Value(n) => write!(f, "{}", n),
+	    ArrayBound::TemplateParam(n) => write!(f, "T{}", n)
+	}
     }
+}
 
-    #[test]
-    fn mangle_nested() {
-	use super::{
-	    Type,
-	    Func
-	};
-	use alloc::string::String;
-	use alloc::vec;
-	let f = Func::new(String::from("myNamespace::inner::func"), vec![Type::Int, Type::Double, Type::Ellipsis]);
-	let h = Func::new(String::from("hello::world"), vec![Type::Void]);
-	assert_eq!(&f.mangle(), "_ZN11myNamespace5inner4funcEidz");
-	assert_eq!(&h.mangle(), "_ZN5hello5worldEv");
+/// Ref-qualifier of a member function
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RefQualifier {
+    /// &
+    LValue, // R
+    /// &&
+    RValue // O
+}
+
+impl RefQualifier {
+    fn mangle(&self, s: &mut String) {
+	s.push(match self {
+	    RefQualifier::LValue => 'R',
+	    RefQualifier::RValue => 'O'
+	});
+    }
+}
+
+impl Display for RefQualifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	f.write_str(match self {
+	    RefQualifier::LValue => "&",
+	    RefQualifier::RValue => "&&"
+	})
+    }
+}
+
+/// Type of a function, as pointed to by a function pointer or given as a template argument
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FunctionType {
+    /// return type
+    pub ret: Box<Type>,
+    /// parameters, Type::Void alone for none
+    pub params: Vec<Type>,
+    /// noexcept function
+    pub is_noexcept: bool,
+    /// function with extern "C" language linkage
+    pub is_extern_c: bool,
+    /// cv-qualifiers, as in the type of a const member function
+    pub quals: Qualifiers,
+    /// ref-qualifier, as in the type of a && member function
+    pub ref_qual: Option<RefQualifier>
+}
+
+impl FunctionType {
+    /// function type returning ret
+    pub fn new(ret: Type, params: Vec<Type>) -> Self {
+	Self {
+	    ret: Box::new(ret),
+	    params,
+	    is_noexcept: false,
+	    is_extern_c: false,
+	    quals: Qualifiers::NONE,
+	    ref_qual: None
+	}
+    }
+
+    /// make the function noexcept
+    pub fn noexcept(mut self) -> Self {
+	self.is_noexcept = true;
+	self
+    }
+
+    /// give the function extern "C" language linkage
+    pub fn extern_c(mut self) -> Self {
+	self.is_extern_c = true;
+	self
+    }
+
+    /// cv-qualify the function type
+    pub fn with_qualifiers(mut self, q: Qualifiers) -> Self {
+	self.quals = q;
+	self
+    }
+
+    /// ref-qualify the function type
+    pub fn with_ref_qualifier(mut self, r: RefQualifier) -> Self {
+	self.ref_qual = Some(r);
+	self
+    }
+
+    fn validate(&self) -> Result<(), MangleError> {
+	validate_params(&self.params)?;
+	for t in &self.params {
+	    t.validate()?;
+	}
+	validate_return(&self.ret)
+    }
+
+    /// <function-type> ::= [<CV-qualifiers>] [Do] F [Y] <type> <bare-function-type> [<ref-qualifier>] E
+    fn mangle(&self, m: &mut Mangler) {
+	self.quals.mangle(&mut m.s);
+	if self.is_noexcept {
+	    m.s.push_str("Do");
+	}
+	m.s.push('F');
+	if self.is_extern_c {
+	    m.s.push('Y');
+	}
+	self.ret.mangle(m);
+	bare_function_type(m, &self.params);
+	if let Some(r) = self.ref_qual {
+	    r.mangle(&mut m.s);
+	}
+	m.s.push('E');
+    }
+}
+
+/// <bare-function-type> ::= <type>+, top level cv-qualifiers are not part of the signature
+fn bare_function_type(m: &mut Mangler, params: &[Type]) {
+    for i in params {
+	i.adjusted().mangle(m);
+    }
+}
+
+/// parameter list in parentheses as adjusted in the signature, empty for void alone
+fn fmt_params<W: Write>(w: &mut W, params: &[Type]) -> fmt::Result {
+    w.write_char('(')?;
+    if params != [Type::Void] {
+	for (n, i) in params.iter().enumerate() {
+	    if n > 0 {
+		w.write_str(", ")?;
+	    }
+	    write!(w, "{}", i.adjusted())?;
+	}
+    }
+    w.write_char(')')
+}
+
+/// check that a function can return the type, which cannot be an array or function
+fn validate_return(t: &Type) -> Result<(), MangleError> {
+    match t {
+	Type::Ellipsis | Type::Array(..) | Type::Function(_) => Err(MangleError::InvalidReturn),
+	_ => t.validate()
+    }
+}
+
+/// check that void is the sole parameter and ... the last if present
+fn validate_params(params: &[Type]) -> Result<(), MangleError> {
+    if params.is_empty() {
+	return Err(MangleError::NoParameters);
+    }
+    if params.len() > 1 && params.contains(&Type::Void) {
+	return Err(MangleError::MisplacedVoid);
+    }
+    if params.iter().rev().skip(1).any(|p| *p == Type::Ellipsis) {
+	return Err(MangleError::MisplacedEllipsis);
+    }
+    Ok(())
+}
+
+/// Argument of a template specialization
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum TemplateArg {
+    /// type argument
+    Type(Type),
+    /// integer literal of the given integral type, e.g. the 4 in Vec<float, 4>
+    Literal(Type, i128) // L <type> <value> E
+}
+
+impl TemplateArg {
+    fn mangle(&self, m: &mut Mangler) {
+	match self {
+	    TemplateArg::Type(t) => t.mangle(m),
+	    TemplateArg::Literal(t, v) => {
+		m.s.push('L');
+		t.mangle(m);
+		if *v < 0 {
+		    m.s.push('n');
+		}
+		let _ = write!(m.s, "{}", v.unsigned_abs());
+		m.s.push('E');
+	    }
+	}
+    }
+}
+
+impl Display for TemplateArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    TemplateArg::Type(t) => write!(f, "{}", t),
+	    TemplateArg::Literal(t, v) => match t {
+		Type::Bool => f.write_str(if *v == 0 { "false" } else { "true" }),
+		Type::Int => write!(f, "{}", v),
+		Type::UInt => write!(f, "{}u", v),
+		Type::Long => write!(f, "{}l", v),
+		Type::ULong => write!(f, "{}ul", v),
+		Type::LLong => write!(f, "{}ll", v),
+		Type::ULLong => write!(f, "{}ull", v),
+		t => write!(f, "({}){}", t, v)
+	    }
+	}
+    }
+}
+
+/// <template-args> ::= I <template-arg>+ E
+fn template_args(m: &mut Mangler, args: &[TemplateArg]) {
+    m.s.push('I');
+    for i in args {
+	i.mangle(m);
+    }
+    m.s.push('E');
+}
+
+/// Variant of a constructor
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CtorKind {
+    /// complete object constructor
+    Complete, // C1
+    /// base object constructor
+    Base, // C2
+    /// complete object allocating constructor
+    Allocating // C3
+}
+
+/// Variant of a destructor
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DtorKind {
+    /// deleting destructor
+    Deleting, // D0
+    /// complete object destructor
+    Complete, // D1
+    /// base object destructor
+    Base // D2
+}
+
+/// Unqualified name of a scope component
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum UnqualifiedName {
+    /// identifier
+    Source(String), // <source-name>
+    /// constructor of the enclosing class
+    Ctor(CtorKind), // C1 C2 C3
+    /// destructor of the enclosing class
+    Dtor(DtorKind), // D0 D1 D2
+    /// overloaded operator
+    Operator(Operator), // <operator-name>
+    /// conversion operator to the type
+    Conversion(Box<Type>), // cv <type>
+    /// user defined literal suffix, as in operator"" _km
+    Literal(String), // li <source-name>
+    /// identifier with internal linkage, as a static function or variable
+    Internal(String), // L <source-name>
+    /// unnamed namespace, written (anonymous namespace)
+    AnonymousNamespace // 12_GLOBAL__N_1
+}
+
+impl UnqualifiedName {
+    /// parse identifier or operator name such as operator+ or operator new
+    fn new(n: &str) -> Self {
+	if n == "(anonymous namespace)" {
+	    return Self::AnonymousNamespace;
+	}
+	if let Some(rest) = n.strip_prefix("operator") {
+	    // keyword operators must be separated, operators is an identifier
+	    let separated = rest.starts_with(char::is_whitespace) || !rest.starts_with(|c: char| c == '_' || c.is_ascii_alphanumeric());
+	    let sym = rest.trim();
+	    if separated {
+		if let Some(o) = Operator::from_symbol(sym) {
+		    return Self::Operator(o);
+		}
+		if let Some(suffix) = sym.strip_prefix("\"\"") {
+		    return Self::Literal(String::from(suffix.trim_start()));
+		}
+	    }
+	}
+	Self::Source(String::from(n))
+    }
+
+    fn mangle(&self, m: &mut Mangler) {
+	let s = &mut m.s;
+	match self {
+	    UnqualifiedName::Source(n) => source_name(s, n),
+	    UnqualifiedName::Ctor(k) => s.push_str(match k {
+		CtorKind::Complete => "C1",
+		CtorKind::Base => "C2",
+		CtorKind::Allocating => "C3"
+	    }),
+	    UnqualifiedName::Dtor(k) => s.push_str(match k {
+		DtorKind::Deleting => "D0",
+		DtorKind::Complete => "D1",
+		DtorKind::Base => "D2"
+	    }),
+	    UnqualifiedName::Operator(o) => s.push_str(o.code()),
+	    UnqualifiedName::Conversion(t) => {
+		s.push_str("cv");
+		t.mangle(m);
+	    },
+	    UnqualifiedName::Literal(n) => {
+		s.push_str("li");
+		source_name(s, n);
+	    },
+	    UnqualifiedName::Internal(n) => {
+		s.push('L');
+		source_name(s, n);
+	    },
+	    UnqualifiedName::AnonymousNamespace => source_name(s, "_GLOBAL__N_1")
+	}
+    }
+}
+
+/// Component of a scoped name
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Component {
+    /// unqualified name
+    pub name: UnqualifiedName,
+    /// template arguments if the component is a template specialization
+    pub template_args: Option<Vec<TemplateArg>>,
+    /// ABI tags, as in [[gnu::abi_tag("cxx11")]], sorted and without duplicates
+    pub abi_tags: Vec<String>
+}
+
+impl Component {
+    /// plain identifier
+    pub fn new(name: String) -> Self {
+	Self::from(UnqualifiedName::Source(name))
+    }
+
+    /// parse unqualified name followed by ABI tags written as in f[abi:cxx11]
+    fn parse(mut n: &str) -> Self {
+	let mut tags = Vec::new();
+	while let Some(rest) = n.strip_suffix(']')
+	    && let Some((name, tag)) = rest.rsplit_once("[abi:") {
+	    tags.push(String::from(tag));
+	    n = name;
+	}
+	Self::from(UnqualifiedName::new(n)).with_abi_tags(tags)
+    }
+
+    /// add ABI tags to the name
+    pub fn with_abi_tags(mut self, tags: Vec<String>) -> Self {
+	self.add_abi_tags(tags);
+	self
+    }
+
+    fn add_abi_tags(&mut self, tags: Vec<String>) {
+	self.abi_tags.extend(tags);
+	self.abi_tags.sort();
+	self.abi_tags.dedup();
+    }
+
+    /// <unqualified-name> [<abi-tags>], <abi-tag> ::= B <source-name>
+    fn mangle_name(&self, m: &mut Mangler) {
+	self.name.mangle(m);
+	for i in &self.abi_tags {
+	    m.s.push('B');
+	    source_name(&mut m.s, i);
+	}
+    }
+
+    /// specialization of the template named by this component
+    pub fn with_template_args(mut self, args: Vec<TemplateArg>) -> Self {
+	self.template_args = Some(args);
+	self
+    }
+
+    fn fmt_template_args(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	if let Some(a) = &self.template_args {
+	    let mut s = String::new();
+	    for (n, i) in a.iter().enumerate() {
+		if n > 0 {
+		    s.push_str(", ");
+		}
+		write!(s, "{}", i)?;
+	    }
+	    // keep nested closing brackets apart, as c++filt does
+	    let space = if s.ends_with('>') { " " } else { "" };
+	    write!(f, "<{}{}>", s, space)?;
+	}
+	Ok(())
+    }
+
+    fn fmt_abi_tags(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	for i in &self.abi_tags {
+	    write!(f, "[abi:{}]", i)?;
+	}
+	Ok(())
+    }
+}
+
+impl From<UnqualifiedName> for Component {
+    fn from(name: UnqualifiedName) -> Self {
+	Self {
+	    name,
+	    template_args: None,
+	    abi_tags: Vec::new()
+	}
+    }
+}
+
+/// Scoped C++ name of a function or type
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum Scope {
+    /// name in the global namespace
+    Unscoped(Component),
+    /// name inside namespaces or classes, outermost first
+    Nested(Vec<Component>), //N ... E,
+}
+
+impl Scope {
+    /// split full C++ name on ::
+    pub fn new(n: String) -> Self {
+	Self::from_components(n.split("::").map(Component::parse).collect())
+    }
+
+    /// name from its components, outermost first
+    pub fn from_components(mut c: Vec<Component>) -> Self {
+	if c.len() == 1 {
+	    Self::Unscoped(c.remove(0))
+	} else {
+	    Self::Nested(c)
+	}
+    }
+
+    /// specialization of the template named by the final component
+    pub fn with_template_args(mut self, args: Vec<TemplateArg>) -> Self {
+	if let Some(c) = self.components_mut().last_mut() {
+	    c.template_args = Some(args);
+	}
+	self
+    }
+
+    fn components(&self) -> &[Component] {
+	match self {
+	    Scope::Unscoped(st) => core::slice::from_ref(st),
+	    Scope::Nested(b) => b
+	}
+    }
+
+    fn components_mut(&mut self) -> &mut [Component] {
+	match self {
+	    Scope::Unscoped(st) => core::slice::from_mut(st),
+	    Scope::Nested(b) => b
+	}
+    }
+
+    /// add ABI tags to the final component
+    pub fn with_abi_tags(mut self, tags: Vec<String>) -> Self {
+	if let Some(c) = self.components_mut().last_mut() {
+	    c.add_abi_tags(tags);
+	}
+	self
+    }
+
+    /// give the final component internal linkage, as if declared static
+    pub fn with_internal_linkage(mut self) -> Self {
+	if let Some(c) = self.components_mut().last_mut()
+	    && let UnqualifiedName::Source(n) = &mut c.name {
+	    c.name = UnqualifiedName::Internal(core::mem::take(n));
+	}
+	self
+    }
+
+    /// ABI tags of the components and their template arguments, with the cxx11 tag
+    /// of the inline namespace std::__cxx11 which is not mangled
+    fn abi_tags(&self, tags: &mut Vec<String>) {
+	let c = self.components();
+	if let [std, ns, ..] = c
+	    && std.name == UnqualifiedName::Source(String::from("std"))
+	    && ns.name == UnqualifiedName::Source(String::from("__cxx11")) {
+	    tags.push(String::from("cxx11"));
+	}
+	for i in c {
+	    tags.extend(i.abi_tags.iter().cloned());
+	    for a in i.template_args.iter().flatten() {
+		match a {
+		    TemplateArg::Type(t) | TemplateArg::Literal(t, _) => t.abi_tags(tags)
+		}
+	    }
+	}
+    }
+
+    /// template arguments of the final component, which template parameters refer to
+    fn template_args(&self) -> &[TemplateArg] {
+	self.components().last().and_then(|c| c.template_args.as_deref()).unwrap_or(&[])
+    }
+
+    /// name with template parameters in template arguments and conversion types replaced
+    fn resolve(&self, args: &[TemplateArg]) -> Result<Scope, MangleError> {
+	let mut s = self.clone();
+	for i in s.components_mut() {
+	    if let UnqualifiedName::Conversion(t) = &mut i.name {
+		**t = t.resolve(args)?;
+	    }
+	    for a in i.template_args.iter_mut().flatten() {
+		match a {
+		    TemplateArg::Type(t) | TemplateArg::Literal(t, _) => *t = t.resolve(args)?
+		}
+	    }
+	}
+	Ok(s)
+    }
+
+    /// whether the final component is a template specialization
+    fn is_template(&self) -> bool {
+	self.components().last().is_some_and(|c| c.template_args.is_some())
+    }
+
+    /// whether the final component is a constructor, destructor or conversion operator,
+    /// which have no return type even as templates
+    fn omits_return(&self) -> bool {
+	self.components().last().is_some_and(|c| matches!(c.name, UnqualifiedName::Ctor(_) | UnqualifiedName::Dtor(_) | UnqualifiedName::Conversion(_)))
+    }
+
+    /// mangle name, the final component is only a substitution candidate when naming a type
+    fn mangle(&self, m: &mut Mangler, is_type: bool) {
+	self.mangle_member(m, is_type, Qualifiers::NONE, None);
+    }
+
+    /// mangle name with the qualifiers of a member function, which only nested names carry
+    /// components as declared by the standard library, libc++ declares everything in std
+    /// within the inline namespace std::__1
+    fn lib_components(&self, std_lib: StdLib) -> Cow<'_, [Component]> {
+	let mut c = Cow::Borrowed(self.components());
+	if std_lib == StdLib::LibCxx
+	    && let [std, next, ..] = &c[..]
+	    && std.name == UnqualifiedName::Source(String::from("std"))
+	    && next.name != UnqualifiedName::Source(String::from("__1")) {
+	    c.to_mut().insert(1, Component::new(String::from("__1")));
+	}
+	c
+    }
+
+    /// whether the name is mangled as a <nested-name>, which alone can carry qualifiers
+    fn is_nested(&self, std_lib: StdLib) -> bool {
+	let c = self.lib_components(std_lib);
+	is_nested(&c, std_abbreviation(&c).map_or(0, |a| a.1))
+    }
+
+    fn mangle_member(&self, m: &mut Mangler, is_type: bool, q: Qualifiers, r: Option<RefQualifier>) {
+	// each component is a prefix, followed by another if it has template arguments,
+	// an abbreviation of the start of a name in std is a step without a component
+	let c = self.lib_components(m.std_lib);
+	let mut steps = Vec::new();
+	let mut key = Mangler::plain(m.std_lib);
+	let (abbreviated, with_args) = match std_abbreviation(&c) {
+	    Some((a, n, with_args)) => {
+		key.s.push_str(a);
+		steps.push((None, false, key.s.clone()));
+		(n, with_args)
+	    },
+	    None => (0, false)
+	};
+	for (n, i) in c.iter().enumerate().skip(abbreviated.saturating_sub(1)) {
+	    if n >= abbreviated {
+		i.mangle_name(&mut key);
+		steps.push((Some(i), false, key.s.clone()));
+	    }
+	    if let Some(a) = &i.template_args
+		&& (n >= abbreviated || !with_args) {
+		template_args(&mut key, a);
+		steps.push((Some(i), true, key.s.clone()));
+	    }
+	}
+	// nothing to mangle for a name without components, which validate rejects
+	let Some(last) = steps.len().checked_sub(1) else {
+	    return;
+	};
+	if is_type && m.substitute(&steps[last].2) {
+	    return;
+	}
+	let nested = is_nested(&c, abbreviated);
+	if nested {
+	    m.s.push('N');
+	    q.mangle(&mut m.s);
+	    if let Some(r) = r {
+		r.mangle(&mut m.s);
+	    }
+	}
+	// continue after the longest prefix already in the substitution table
+	let start = (0..last).rev().find(|n| m.substitute(&steps[*n].2)).map_or(0, |n| n + 1);
+	for (n, (c, args, key)) in steps.into_iter().enumerate().skip(start) {
+	    let Some(c) = c else {
+		// substitutions are not candidates themselves
+		m.s.push_str(&key);
+		continue;
+	    };
+	    match &c.template_args {
+		Some(a) if args => template_args(m, a),
+		_ => c.mangle_name(m)
+	    }
+	    // a function template's name without arguments is still a candidate
+	    if is_type || n < last {
+		m.add(key);
+	    }
+	}
+	if nested {
+	    m.s.push('E');
+	}
+    }
+}
+
+impl Scope {
+    fn validate(&self) -> Result<(), MangleError> {
+	let c = self.components();
+	if c.is_empty() || (c.len() == 1 && c[0].name == UnqualifiedName::Source(String::new())) {
+	    return Err(MangleError::EmptyName);
+	}
+	if let [std, typedef, ..] = c
+	    && std.name == UnqualifiedName::Source(String::from("std"))
+	    && let UnqualifiedName::Source(n) = &typedef.name
+	    && STD_TYPEDEFS.contains(&n.as_str()) {
+	    return Err(MangleError::StdTypedef(n.clone()));
+	}
+	// constructors and destructors need an enclosing class
+	if matches!(c[0].name, UnqualifiedName::Ctor(_) | UnqualifiedName::Dtor(_)) {
+	    return Err(MangleError::MisplacedStructor);
+	}
+	for (n, i) in c.iter().enumerate() {
+	    match &i.name {
+		UnqualifiedName::Source(s) if s.is_empty() => return Err(MangleError::EmptyComponent(n)),
+		UnqualifiedName::Source(s) | UnqualifiedName::Literal(s) | UnqualifiedName::Internal(s) if !is_identifier(s) => {
+		    return Err(MangleError::InvalidIdentifier(s.clone()));
+		},
+		UnqualifiedName::Conversion(t) => t.validate()?,
+		_ => ()
+	    }
+	    if let Some(t) = i.abi_tags.iter().find(|t| !is_identifier(t)) {
+		return Err(MangleError::InvalidIdentifier(t.clone()));
+	    }
+	    for a in i.template_args.iter().flatten() {
+		match a {
+		    TemplateArg::Type(t) | TemplateArg::Literal(t, _) => t.validate()?
+		}
+	    }
+	}
+	Ok(())
+    }
+}
+
+/// whether components c, the first abbreviated of them abbreviated, make a <nested-name>,
+/// St only qualifies the component after it, the other abbreviations name a class
+fn is_nested(c: &[Component], abbreviated: usize) -> bool {
+    c.len() - abbreviated + usize::from(abbreviated > 1) > 1
+}
+
+/// <substitution> ::= Sa | Sb | Ss | Si | So | Sd, with the class in std it names and
+/// how many of the char specialization's template arguments it includes
+const STD_SUBSTITUTIONS: [(&str, &str, usize); 6] = [
+    ("Ss", "basic_string", 3),
+    ("Si", "basic_istream", 2),
+    ("So", "basic_ostream", 2),
+    ("Sd", "basic_iostream", 2),
+    ("Sa", "allocator", 0),
+    ("Sb", "basic_string", 0)
+];
+
+/// typedefs in std of string and stream specializations, which never appear in symbols
+const STD_TYPEDEFS: [&str; 13] = [
+    "string",
+    "wstring",
+    "u8string",
+    "u16string",
+    "u32string",
+    "string_view",
+    "wstring_view",
+    "istream",
+    "ostream",
+    "iostream",
+    "wistream",
+    "wostream",
+    "wiostream"
+];
+
+/// name abbreviated by S followed by code
+fn std_substitution(code: u8) -> Option<Scope> {
+    let (_, name, args) = STD_SUBSTITUTIONS.iter().find(|s| s.0.as_bytes()[1] == code)?;
+    Some(Scope::from_components(vec![Component::new(String::from("std")), std_component(name, *args)]))
+}
+
+/// class in std, specialized as in std::basic_string<char, std::char_traits<char>, std::allocator<char> >
+/// if args is not 0
+fn std_component(name: &str, args: usize) -> Component {
+    let c = Component::new(String::from(name));
+    if args == 0 {
+	return c;
+    }
+    let class = |n: &str| TemplateArg::Type(Type::Named(Scope::from_components(vec![
+	Component::new(String::from("std")),
+	Component::new(String::from(n)).with_template_args(vec![TemplateArg::Type(Type::Char)])
+    ])));
+    c.with_template_args([TemplateArg::Type(Type::Char), class("char_traits"), class("allocator")][..args].to_vec())
+}
+
+/// abbreviation of the start of a name in std, with the number of components it covers
+/// and whether it also covers the template arguments of the last of them
+fn std_abbreviation(c: &[Component]) -> Option<(&'static str, usize, bool)> {
+    let [std, name, ..] = c else {
+	return None;
+    };
+    if !matches!(&std.name, UnqualifiedName::Source(s) if s == "std") || std.template_args.is_some() {
+	return None;
+    }
+    for (code, class, args) in STD_SUBSTITUTIONS {
+	let abbreviated = std_component(class, args);
+	if args > 0 && *name == abbreviated {
+	    return Some((code, 2, true));
+	}
+	if args == 0 && name.name == abbreviated.name {
+	    return Some((code, 2, false));
+	}
+    }
+    Some(("St", 1, false))
+}
+
+/// [_a-zA-Z][_a-zA-Z0-9]* allowing other alphabetic and numeric unicode characters
+fn is_identifier(s: &str) -> bool {
+    let mut c = s.chars();
+    c.next().is_some_and(|c| c == '_' || c.is_alphabetic()) && c.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	let c = self.components();
+	for (n, i) in c.iter().enumerate() {
+	    if n > 0 {
+		f.write_str("::")?;
+	    }
+	    // constructors and destructors are named after their class
+	    let class = match n.checked_sub(1).map(|p| &c[p].name) {
+		Some(UnqualifiedName::Source(class)) => class.as_str(),
+		_ => ""
+	    };
+	    match &i.name {
+		UnqualifiedName::Source(s) | UnqualifiedName::Internal(s) => f.write_str(s)?,
+		UnqualifiedName::AnonymousNamespace => f.write_str("(anonymous namespace)")?,
+		UnqualifiedName::Ctor(_) => f.write_str(class)?,
+		UnqualifiedName::Dtor(_) => write!(f, "~{}", class)?,
+		UnqualifiedName::Operator(o) => write!(f, "{}", o)?,
+		UnqualifiedName::Conversion(t) => {
+		    // a conversion template converts to a type given by its own arguments
+		    let args = i.template_args.as_deref().unwrap_or(&[]);
+		    write!(f, "operator {}", t.resolve(args).unwrap_or_else(|_| (**t).clone()))?
+		},
+		UnqualifiedName::Literal(n) => write!(f, "operator\"\" {}", n)?
+	    }
+	    i.fmt_abi_tags(f)?;
+	    i.fmt_template_args(f)?;
+	}
+	Ok(())
+    }
+}
+
+/// <source-name> ::= <length> <identifier>
+fn source_name(s: &mut String, n: &str) {
+    let _ = write!(s, "{}", n.len());
+    s.push_str(n);
+}
+
+/// C++ standard library whose names in std are being mangled
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[non_exhaustive]
+pub enum StdLib {
+    /// GNU libstdc++, names in std are mangled as written
+    #[default]
+    LibStdCxx,
+    /// LLVM libc++, names in std are in the inline namespace std::__1
+    LibCxx
+}
+
+static DEFAULT_STD_LIB: AtomicU8 = AtomicU8::new(StdLib::LibStdCxx as u8);
+
+impl StdLib {
+    /// standard library used unless a Func or Var specifies one, libstdc++ unless changed
+    pub fn global() -> Self {
+	match DEFAULT_STD_LIB.load(Ordering::Relaxed) {
+	    n if n == StdLib::LibCxx as u8 => StdLib::LibCxx,
+	    _ => StdLib::LibStdCxx
+	}
+    }
+
+    /// set standard library used unless a Func or Var specifies one
+    pub fn set_global(self) {
+	DEFAULT_STD_LIB.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+/// State for mangling a single symbol, including the substitution table
+struct Mangler {
+    s: String,
+    subs: Vec<String>,
+    compress: bool,
+    std_lib: StdLib
+}
+
+impl Mangler {
+    /// mangler for a symbol using std_lib, or the global one if None
+    fn new(std_lib: Option<StdLib>) -> Self {
+	Self {
+	    s: String::from("_Z"),
+	    subs: Vec::new(),
+	    compress: true,
+	    std_lib: std_lib.unwrap_or_else(StdLib::global)
+	}
+    }
+
+    /// mangler which never substitutes, used to compute substitution keys
+    fn plain(std_lib: StdLib) -> Self {
+	Self {
+	    s: String::new(),
+	    subs: Vec::new(),
+	    compress: false,
+	    std_lib
+	}
+    }
+
+    /// emit S_ or S<seq-id>_ if key is already in the substitution table
+    fn substitute(&mut self, key: &str) -> bool {
+	if !self.compress {
+	    return false;
+	}
+	match self.subs.iter().position(|k| k == key) {
+	    Some(i) => {
+		self.s.push('S');
+		if i > 0 {
+		    seq_id(&mut self.s, i - 1);
+		}
+		self.s.push('_');
+		true
+	    },
+	    None => false
+	}
+    }
+
+    /// record a new substitution candidate
+    fn add(&mut self, key: String) {
+	if self.compress && !self.subs.contains(&key) {
+	    self.subs.push(key);
+	}
+    }
+}
+
+/// <seq-id> is base 36 using digits and upper case letters
+fn seq_id(s: &mut String, mut n: usize) {
+    let mut digits = Vec::new();
+    loop {
+	let d = (n % 36) as u8;
+	digits.push(if d < 10 { b'0' + d } else { b'A' + d - 10 } as char);
+	n /= 36;
+	if n == 0 {
+	    break;
+	}
+    }
+    digits.iter().rev().for_each(|c| s.push(*c));
+}
+
+/// Reason a Func cannot be mangled
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum MangleError {
+    /// name is empty
+    EmptyName,
+    /// component at the index is empty, as in a::::b
+    EmptyComponent(usize),
+    /// not a valid C++ identifier
+    InvalidIdentifier(String),
+    /// no parameters given, use Type::Void for a function without parameters
+    NoParameters,
+    /// void is only valid as the sole parameter
+    MisplacedVoid,
+    /// ... is only valid as the last parameter
+    MisplacedEllipsis,
+    /// cv or ref qualifiers on a function which is not a member
+    QualifiedNonMember,
+    /// return type which cannot be returned, or set on a constructor or destructor, or
+    /// different from the type a conversion operator converts to
+    InvalidReturn,
+    /// template parameter with the index is not a type argument of the function template
+    InvalidTemplateParam(u32),
+    /// typedef in std such as std::string, which symbols spell as the class template
+    /// specialization it names
+    StdTypedef(String),
+    /// constructor or destructor outside of a class
+    MisplacedStructor
+}
+
+impl Display for MangleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    MangleError::EmptyName => f.write_str("name is empty"),
+	    MangleError::EmptyComponent(n) => write!(f, "component {} of name is empty", n),
+	    MangleError::InvalidIdentifier(s) => write!(f, "invalid identifier {:?}", s),
+	    MangleError::NoParameters => f.write_str("no parameters, use void for none"),
+	    MangleError::MisplacedVoid => f.write_str("void must be the only parameter"),
+	    MangleError::MisplacedEllipsis => f.write_str("... must be the last parameter"),
+	    MangleError::QualifiedNonMember => f.write_str("qualifiers on a function which is not a member"),
+	    MangleError::InvalidReturn => f.write_str("invalid return type"),
+	    MangleError::InvalidTemplateParam(n) => write!(f, "template parameter {} has no argument of the right kind", n),
+	    MangleError::StdTypedef(s) => write!(f, "std::{} is a typedef, name the specialization instead", s),
+	    MangleError::MisplacedStructor => f.write_str("constructor or destructor outside of a class")
+	}
+    }
+}
+
+impl core::error::Error for MangleError {}
+
+/// Function to mangle
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Func {
+    scope: Scope,
+    ret: Option<Type>,
+    params: Vec<Type>,
+    quals: Qualifiers,
+    ref_qual: Option<RefQualifier>,
+    std_lib: Option<StdLib>
+}
+
+impl Func {
+    /// create function to mangle
+    ///
+    /// name - full C++ name of function
+    ///
+    /// params - parameters of function
+    ///
+    /// operators may be named as in operator+, +, -, & and * are taken to be unary
+    /// when there is no operand besides an implicit this, or a single operand outside of
+    /// any namespace or class, and binary otherwise, use with_operator for a unary
+    /// operator in a namespace
+    pub fn new(name: String, params: Vec<Type>) -> Self {
+	let mut scope = Scope::new(name);
+	let operands = if params == [Type::Void] { 0 } else { params.len() };
+	let unscoped = matches!(scope, Scope::Unscoped(_));
+	if let Some(c) = scope.components_mut().last_mut()
+	    && let UnqualifiedName::Operator(o) = c.name
+	    && (operands == 0 || (unscoped && operands == 1)) {
+	    c.name = UnqualifiedName::Operator(o.unary());
+	}
+	Self::from_scope(scope, params)
+    }
+
+    /// create function to mangle from an already scoped name
+    pub fn from_scope(scope: Scope, params: Vec<Type>) -> Self {
+	Self {
+	    scope,
+	    ret: None,
+	    params,
+	    quals: Qualifiers::NONE,
+	    ref_qual: None,
+	    std_lib: None
+	}
+    }
+
+    /// create function to mangle, checking that it can be mangled
+    ///
+    /// name - full C++ name of function
+    ///
+    /// params - parameters of function
+    pub fn try_new(name: String, params: Vec<Type>) -> Result<Self, MangleError> {
+	let f = Self::new(name, params);
+	f.validate()?;
+	Ok(f)
+    }
+
+    /// check that the function can be mangled, including anything set after construction
+    pub fn validate(&self) -> Result<(), MangleError> {
+	self.scope.validate()?;
+	validate_params(&self.params)?;
+	if (!self.quals.is_empty() || self.ref_qual.is_some()) && !self.scope.is_nested(self.std_lib.unwrap_or_else(StdLib::global)) {
+	    return Err(MangleError::QualifiedNonMember);
+	}
+	for t in &self.params {
+	    t.validate()?;
+	}
+	for t in self.params.iter().chain(self.ret.iter()) {
+	    t.resolve(self.scope.template_args())?;
+	}
+	let Some(r) = &self.ret else {
+	    return Ok(());
+	};
+	// constructors and destructors return nothing, conversion operators their target
+	match self.scope.components().last().map(|c| &c.name) {
+	    Some(UnqualifiedName::Ctor(_) | UnqualifiedName::Dtor(_)) => Err(MangleError::InvalidReturn),
+	    Some(UnqualifiedName::Conversion(t)) if **t != *r => Err(MangleError::InvalidReturn),
+	    _ => validate_return(r)
+	}
+    }
+
+    /// create constructor to mangle
+    ///
+    /// class - full C++ name of the class
+    ///
+    /// kind - which constructor variant the symbol is for
+    ///
+    /// params - parameters of constructor
+    pub fn constructor(class: String, kind: CtorKind, params: Vec<Type>) -> Self {
+	Self::structor(class, UnqualifiedName::Ctor(kind), params)
+    }
+
+    /// create destructor to mangle
+    ///
+    /// class - full C++ name of the class
+    ///
+    /// kind - which destructor variant the symbol is for
+    pub fn destructor(class: String, kind: DtorKind) -> Self {
+	Self::structor(class, UnqualifiedName::Dtor(kind), vec![Type::Void])
+    }
+
+    /// create conversion operator to mangle
+    ///
+    /// class - full C++ name of the class
+    ///
+    /// to - type converted to
+    pub fn conversion(class: String, to: Type) -> Self {
+	Self::structor(class, UnqualifiedName::Conversion(Box::new(to)), vec![Type::Void])
+    }
+
+    /// member with a special unqualified name
+    fn structor(class: String, name: UnqualifiedName, params: Vec<Type>) -> Self {
+	let mut c = Scope::new(class).components().to_vec();
+	c.push(Component::from(name));
+	Self {
+	    scope: Scope::Nested(c),
+	    ret: None,
+	    params,
+	    quals: Qualifiers::NONE,
+	    ref_qual: None,
+	    std_lib: None
+	}
+    }
+
+    /// make the function a specialization of a function template
+    pub fn with_template_args(mut self, args: Vec<TemplateArg>) -> Self {
+	self.scope = self.scope.with_template_args(args);
+	self
+    }
+
+    /// make the function the operator o, for a unary +, -, & or * in a namespace which
+    /// takes its single operand as a parameter rather than as this
+    pub fn with_operator(mut self, o: Operator) -> Self {
+	if let Some(c) = self.scope.components_mut().last_mut() {
+	    c.name = UnqualifiedName::Operator(o);
+	}
+	self
+    }
+
+    /// add ABI tags to the function name, those of the return type are added implicitly
+    pub fn with_abi_tags(mut self, tags: Vec<String>) -> Self {
+	self.scope = self.scope.with_abi_tags(tags);
+	self
+    }
+
+    /// give the function internal linkage, as a static function
+    pub fn with_internal_linkage(mut self) -> Self {
+	self.scope = self.scope.with_internal_linkage();
+	self
+    }
+
+    /// cv-qualify a member function, as in int get() const
+    pub fn with_qualifiers(mut self, q: Qualifiers) -> Self {
+	self.quals = q;
+	self
+    }
+
+    /// ref-qualify a member function, as in int get() &&
+    pub fn with_ref_qualifier(mut self, r: RefQualifier) -> Self {
+	self.ref_qual = Some(r);
+	self
+    }
+
+    /// mangle names in std for the standard library, instead of the global one
+    pub fn with_std_lib(mut self, lib: StdLib) -> Self {
+	self.std_lib = Some(lib);
+	self
+    }
+
+    /// set return type, which is only part of the symbol for function templates other than
+    /// constructors, destructors and conversion operators, otherwise it only adds its ABI tags
+    ///
+    /// void is the same as no return type
+    pub fn with_return(mut self, t: Type) -> Self {
+	self.ret = Some(t).filter(|t| *t != Type::Void);
+	self
+    }
+
+    /// return type as encoded for function templates, void if not set
+    fn template_return(&self) -> Option<&Type> {
+	if self.scope.is_template() && !self.scope.omits_return() {
+	    Some(self.ret.as_ref().unwrap_or(&Type::Void))
+	} else {
+	    None
+	}
+    }
+
+    /// Mangle function according to Itanium C++ ABI
+    pub fn mangle(&self) -> String {
+	let mut m = Mangler::new(self.std_lib);
+	self.encode(&mut m);
+	m.s
+    }
+
+    /// name with the ABI tags of the return type that the parameters do not have, unless
+    /// the return type is mangled or implied by the name
+    fn tagged_scope(&self) -> Cow<'_, Scope> {
+	let mut tags = Vec::new();
+	if let Some(r) = &self.ret
+	    && !self.scope.is_template()
+	    && !self.scope.omits_return() {
+	    r.abi_tags(&mut tags);
+	}
+	let mut present = Vec::new();
+	for i in &self.params {
+	    i.abi_tags(&mut present);
+	}
+	tags.retain(|t| !present.contains(t));
+	if tags.is_empty() {
+	    Cow::Borrowed(&self.scope)
+	} else {
+	    Cow::Owned(self.scope.clone().with_abi_tags(tags))
+	}
+    }
+
+    /// <encoding> ::= <name> <bare-function-type>
+    fn encode(&self, m: &mut Mangler) {
+	self.tagged_scope().mangle_member(m, false, self.quals, self.ref_qual);
+	if let Some(r) = self.template_return() {
+	    r.mangle(m);
+	}
+	bare_function_type(m, &self.params);
+    }
+}
+
+/// C++ signature of the function
+impl Display for Func {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	// template parameters are shown as the arguments they refer to
+	let args = self.scope.template_args();
+	let resolve = |t: &Type| t.resolve(args).unwrap_or_else(|_| t.clone());
+	let params: Vec<Type> = self.params.iter().map(resolve).collect();
+	let mut s = format!("{}", self.tagged_scope());
+	fmt_params(&mut s, &params)?;
+	if !self.quals.is_empty() {
+	    write!(s, " {}", self.quals)?;
+	}
+	if let Some(r) = self.ref_qual {
+	    write!(s, " {}", r)?;
+	}
+	// the function is declared within the return type, as in int (*f<char>(int))(int)
+	match self.template_return() {
+	    Some(r) => resolve(r).fmt_declarator(f, &format!(" {}", s)),
+	    None => f.write_str(&s)
+	}
+    }
+}
+
+/// Variable to mangle, at namespace scope, a static data member or a static local
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Var {
+    scope: Scope,
+    func: Option<Box<Func>>,
+    index: u32,
+    std_lib: Option<StdLib>
+}
+
+impl Var {
+    /// create variable to mangle
+    ///
+    /// name - full C++ name of variable
+    pub fn new(name: String) -> Self {
+	Self::from_scope(Scope::new(name))
+    }
+
+    /// create variable to mangle from an already scoped name
+    pub fn from_scope(scope: Scope) -> Self {
+	Self {
+	    scope,
+	    func: None,
+	    index: 0,
+	    std_lib: None
+	}
+    }
+
+    /// create static local variable to mangle
+    ///
+    /// func - function the variable is declared in
+    ///
+    /// name - name of the variable within the function
+    pub fn local(func: Func, name: String) -> Self {
+	Self {
+	    scope: Scope::new(name),
+	    func: Some(Box::new(func)),
+	    index: 0,
+	    std_lib: None
+	}
+    }
+
+    /// distinguish a static local from others of the same name in its function,
+    /// index is 0 for the first such variable, 1 for the second and so on
+    pub fn with_discriminator(mut self, index: u32) -> Self {
+	self.index = index;
+	self
+    }
+
+    /// create variable to mangle, checking that it can be mangled
+    ///
+    /// name - full C++ name of variable
+    pub fn try_new(name: String) -> Result<Self, MangleError> {
+	let v = Self::new(name);
+	v.validate()?;
+	Ok(v)
+    }
+
+    /// check that the variable can be mangled
+    pub fn validate(&self) -> Result<(), MangleError> {
+	if let Some(f) = &self.func {
+	    f.validate()?;
+	}
+	self.scope.validate()
+    }
+
+    /// make the variable a specialization of a variable template
+    pub fn with_template_args(mut self, args: Vec<TemplateArg>) -> Self {
+	self.scope = self.scope.with_template_args(args);
+	self
+    }
+
+    /// add ABI tags to the variable name, including any the type of the variable has
+    pub fn with_abi_tags(mut self, tags: Vec<String>) -> Self {
+	self.scope = self.scope.with_abi_tags(tags);
+	self
+    }
+
+    /// give the variable internal linkage, as a static variable
+    pub fn with_internal_linkage(mut self) -> Self {
+	self.scope = self.scope.with_internal_linkage();
+	self
+    }
+
+    /// mangle names in std for lib instead of the global standard library, or the one of
+    /// the function a static local is in
+    pub fn with_std_lib(mut self, lib: StdLib) -> Self {
+	self.std_lib = Some(lib);
+	self
+    }
+
+    /// function to call for the address of a thread_local variable, initializing it if needed
+    pub fn tls_wrapper(&self) -> SpecialName {
+	SpecialName::TlsWrapper(self.clone())
+    }
+
+    /// function initializing a thread_local variable
+    pub fn tls_init(&self) -> SpecialName {
+	SpecialName::TlsInit(self.clone())
+    }
+
+    /// Mangle variable according to Itanium C++ ABI, variables in the global namespace are not mangled
+    pub fn mangle(&self) -> String {
+	match &self.scope {
+	    Scope::Unscoped(Component { name: UnqualifiedName::Source(n), template_args: None, abi_tags }) if self.func.is_none() && abi_tags.is_empty() => n.clone(),
+	    _ => {
+		let mut m = Mangler::new(self.std_lib());
+		self.encode(&mut m);
+		m.s
+	    }
+	}
+    }
+
+    /// standard library of the variable, or of the function a static local is in
+    fn std_lib(&self) -> Option<StdLib> {
+	self.std_lib.or_else(|| self.func.as_ref().and_then(|f| f.std_lib))
+    }
+
+    /// <name>, a <local-name> for static locals
+    fn encode(&self, m: &mut Mangler) {
+	let Some(f) = &self.func else {
+	    return self.scope.mangle(m, false);
+	};
+	// <local-name> ::= Z <function encoding> E <entity name> [<discriminator>]
+	m.s.push('Z');
+	f.encode(m);
+	m.s.push('E');
+	self.scope.mangle(m, false);
+	match self.index {
+	    0 => (),
+	    n @ 1..=10 => {
+		let _ = write!(m.s, "_{}", n - 1);
+	    },
+	    n => {
+		let _ = write!(m.s, "__{}_", n - 1);
+	    }
+	}
+    }
+}
+
+impl Display for Var {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	if let Some(func) = &self.func {
+	    write!(f, "{}::", func)?;
+	}
+	write!(f, "{}", self.scope)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn mangle_unscoped() {
+	use super::{
+	    Type,
+	    Func
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let f1 = Func::new(String::from("func"), vec![Type::Void]);
+	let f2 = Func::new(String::from("func"), vec![Type::Int]);
+	assert_eq!(&f1.mangle(), "_Z4funcv");
+	assert_eq!(&f2.mangle(), "_Z4funci");
+    }
+
+    #[test]
+    fn mangle_nested() {
+	use super::{
+	    Type,
+	    Func
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let f = Func::new(String::from("myNamespace::inner::func"), vec![Type::Int, Type::Double, Type::Ellipsis]);
+	let h = Func::new(String::from("hello::world"), vec![Type::Void]);
+	assert_eq!(&f.mangle(), "_ZN11myNamespace5inner4funcEidz");
+	assert_eq!(&h.mangle(), "_ZN5hello5worldEv");
+    }
+
+    #[test]
+    fn mangle_pointers() {
+	use super::{
+	    Type,
+	    Func
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let f = Func::new(String::from("f"), vec![Type::pointer(Type::Char), Type::lvalue_ref(Type::Int)]);
+	let g = Func::new(String::from("ns::g"), vec![Type::rvalue_ref(Type::Double), Type::pointer(Type::pointer(Type::Char))]);
+	assert_eq!(&f.mangle(), "_Z1fPcRi");
+	assert_eq!(&g.mangle(), "_ZN2ns1gEOdPPc");
+    }
+
+    #[test]
+    fn mangle_qualifiers() {
+	use super::{
+	    Type,
+	    Func,
+	    Qualifiers
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let f = Func::new(String::from("f"), vec![Type::pointer(Type::constant(Type::Char))]);
+	let g = Func::new(String::from("g"), vec![Type::pointer(Type::volatile(Type::Int)), Type::constant(Type::Int)]);
+	let q = Qualifiers::CONST | Qualifiers::VOLATILE | Qualifiers::RESTRICT;
+	let k = Func::new(String::from("k"), vec![Type::pointer(Type::qualified(q, Type::pointer(Type::Int)))]);
+	assert_eq!(&f.mangle(), "_Z1fPKc");
+	assert_eq!(&g.mangle(), "_Z1gPVii");
+	assert_eq!(&k.mangle(), "_Z1kPrVKPi");
+    }
+
+    #[test]
+    fn mangle_substitutions() {
+	use super::{
+	    Type,
+	    Func
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let p = || Type::pointer(Type::Int);
+	let f = Func::new(String::from("f"), vec![p(), p()]);
+	let g = Func::new(String::from("f"), vec![Type::pointer(Type::constant(Type::Char)), Type::pointer(Type::constant(Type::Char))]);
+	let h = Func::new(String::from("ns::f"), vec![Type::pointer(p()), p(), Type::pointer(p())]);
+	let mut deep = Type::Int;
+	for _ in 0..12 {
+	    deep = Type::pointer(deep);
+	}
+	let k = Func::new(String::from("g"), vec![deep.clone(), deep]);
+	assert_eq!(&f.mangle(), "_Z1fPiS_");
+	assert_eq!(&g.mangle(), "_Z1fPKcS0_");
+	assert_eq!(&h.mangle(), "_ZN2ns1fEPPiS0_S1_");
+	assert_eq!(&k.mangle(), "_Z1gPPPPPPPPPPPPiSA_");
+    }
+
+    #[test]
+    fn mangle_named() {
+	use super::{
+	    Type,
+	    Func
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let widget = || Type::named(String::from("gfx::Widget"));
+	let draw = Func::new(String::from("draw"), vec![widget()]);
+	let f = Func::new(String::from("f"), vec![Type::pointer(Type::named(String::from("Foo"))), Type::pointer(Type::named(String::from("Foo")))]);
+	let bar = Func::new(String::from("Foo::bar"), vec![Type::pointer(Type::named(String::from("Foo")))]);
+	let g = Func::new(String::from("gfx::draw"), vec![Type::lvalue_ref(Type::constant(widget())), Type::pointer(widget())]);
+	assert_eq!(&draw.mangle(), "_Z4drawN3gfx6WidgetE");
+	assert_eq!(&f.mangle(), "_Z1fP3FooS0_");
+	assert_eq!(&bar.mangle(), "_ZN3Foo3barEPS_");
+	assert_eq!(&g.mangle(), "_ZN3gfx4drawERKNS_6WidgetEPS0_");
+    }
+
+    #[test]
+    fn mangle_templates() {
+	use super::{
+	    Type,
+	    Func,
+	    Scope,
+	    Component,
+	    TemplateArg
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let vec4 = Type::Named(Scope::new(String::from("ns::Vec")).with_template_args(vec![TemplateArg::Type(Type::Float), TemplateArg::Literal(Type::Int, 4)]));
+	let boxed = |t| Type::Named(Scope::new(String::from("Box")).with_template_args(vec![TemplateArg::Type(t)]));
+	let use_ = Func::new(String::from("use"), vec![vec4, boxed(boxed(Type::Int)), boxed(Type::Int)]);
+	let inner = Scope::from_components(vec![
+	    Component::new(String::from("Outer")).with_template_args(vec![TemplateArg::Type(Type::Int)]),
+	    Component::new(String::from("In")).with_template_args(vec![TemplateArg::Type(Type::Float)])
+	]);
+	let g = Func::new(String::from("g"), vec![Type::Named(inner)]);
+	let f = Func::new(String::from("m::f"), vec![Type::pointer(Type::Int)]).with_template_args(vec![TemplateArg::Type(Type::Int)]);
+	let lit = Func::new(String::from("lit"), vec![Type::Void]).with_template_args(vec![TemplateArg::Literal(Type::Long, -3)]);
+	let mx = Func::new(String::from("mx"), vec![Type::Int, Type::Int]).with_template_args(vec![TemplateArg::Type(Type::Float)]).with_return(Type::Float);
+	assert_eq!(&use_.mangle(), "_Z3useN2ns3VecIfLi4EEE3BoxIS2_IiEES3_");
+	assert_eq!(&g.mangle(), "_Z1gN5OuterIiE2InIfEE");
+	assert_eq!(&f.mangle(), "_ZN1m1fIiEEvPi");
+	assert_eq!(&lit.mangle(), "_Z3litILln3EEvv");
+	assert_eq!(&mx.mangle(), "_Z2mxIfEfii");
+    }
+
+    #[test]
+    fn mangle_structors() {
+	use super::{
+	    Type,
+	    Func,
+	    CtorKind,
+	    DtorKind,
+	    TemplateArg
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let c1 = Func::constructor(String::from("Foo"), CtorKind::Complete, vec![Type::Int]);
+	let c2 = Func::constructor(String::from("Foo"), CtorKind::Base, vec![Type::pointer(Type::Float)]).with_template_args(vec![TemplateArg::Type(Type::Float)]);
+	let d0 = Func::destructor(String::from("Foo"), DtorKind::Deleting);
+	let d2 = Func::destructor(String::from("ns::Foo"), DtorKind::Base);
+	assert_eq!(&c1.mangle(), "_ZN3FooC1Ei");
+	assert_eq!(&c2.mangle(), "_ZN3FooC2IfEEPf");
+	assert_eq!(&d0.mangle(), "_ZN3FooD0Ev");
+	assert_eq!(&d2.mangle(), "_ZN2ns3FooD2Ev");
+    }
+
+    #[test]
+    fn mangle_operators() {
+	use super::{
+	    Type,
+	    Func,
+	    Scope,
+	    Component,
+	    UnqualifiedName,
+	    Operator
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let vec3 = || Type::named(String::from("Vec3"));
+	let plus = Func::new(String::from("Vec3::operator+"), vec![Type::lvalue_ref(Type::constant(vec3()))]);
+	let neg = Func::new(String::from("Vec3::operator-"), vec![Type::Void]);
+	let free_neg = Func::new(String::from("operator-"), vec![vec3()]);
+	let call = Func::new(String::from("Vec3::operator()"), vec![Type::Int]);
+	let new = Func::new(String::from("operator new"), vec![Type::ULong, Type::Int]);
+	let delete = Func::new(String::from("operator delete[]"), vec![Type::pointer(Type::Void), Type::Int]);
+	let cmp = Func::new(String::from("operator<=>"), vec![vec3(), vec3()]);
+	let km = Func::new(String::from("operator\"\" _km"), vec![Type::ULLong]);
+	let cv = Func::conversion(String::from("Vec3"), Type::pointer(vec3()));
+	let assign = Func::new(String::from("operators::operator="), vec![Type::Int]);
+	assert_eq!(&plus.mangle(), "_ZN4Vec3plERKS_");
+	assert_eq!(&neg.mangle(), "_ZN4Vec3ngEv");
+	assert_eq!(&free_neg.mangle(), "_Zng4Vec3");
+	assert_eq!(&call.mangle(), "_ZN4Vec3clEi");
+	assert_eq!(&new.mangle(), "_Znwmi");
+	assert_eq!(&delete.mangle(), "_ZdaPvi");
+	assert_eq!(&cmp.mangle(), "_Zss4Vec3S_");
+	assert_eq!(&km.mangle(), "_Zli3_kmy");
+	assert_eq!(&cv.mangle(), "_ZN4Vec3cvPS_Ev");
+	assert_eq!(&assign.mangle(), "_ZN9operatorsaSEi");
+	// a single operand in a namespace is taken as binary unless the operator is given
+	let v = || Type::named(String::from("ns::V"));
+	assert_eq!(&Func::new(String::from("ns::operator-"), vec![v(), v()]).mangle(), "_ZN2nsmiENS_1VES0_");
+	assert_eq!(&Func::new(String::from("ns::operator-"), vec![v()]).with_operator(Operator::Negate).mangle(), "_ZN2nsngENS_1VE");
+	assert_eq!(&Func::new(String::from("ns::operator*"), vec![Type::lvalue_ref(v())]).with_operator(Operator::Deref).mangle(), "_ZN2nsdeERNS_1VE");
+	assert_eq!(Scope::new(String::from("a::operator->*")), Scope::Nested(vec![Component::new(String::from("a")), Component::from(UnqualifiedName::Operator(Operator::ArrowStar))]));
+    }
+
+    #[test]
+    fn mangle_member_qualifiers() {
+	use super::{
+	    Type,
+	    Func,
+	    Qualifiers,
+	    RefQualifier,
+	    TemplateArg
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let get = Func::new(String::from("Foo::get"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST);
+	let get_ref = Func::new(String::from("Foo::ref"), vec![Type::Void]).with_ref_qualifier(RefQualifier::LValue);
+	let cv = Func::new(String::from("Foo::cv"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST | Qualifiers::VOLATILE).with_ref_qualifier(RefQualifier::RValue);
+	let this = Func::new(String::from("Foo::self"), vec![Type::pointer(Type::named(String::from("Foo")))]).with_qualifiers(Qualifiers::CONST);
+	let t = Func::new(String::from("Foo::t"), vec![Type::Void]).with_template_args(vec![TemplateArg::Type(Type::Int)]).with_return(Type::Int).with_qualifiers(Qualifiers::CONST);
+	assert_eq!(&get.mangle(), "_ZNK3Foo3getEv");
+	assert_eq!(&get_ref.mangle(), "_ZNR3Foo3refEv");
+	assert_eq!(&cv.mangle(), "_ZNVKO3Foo2cvEv");
+	assert_eq!(&this.mangle(), "_ZNK3Foo4selfEPS_");
+	assert_eq!(&t.mangle(), "_ZNK3Foo1tIiEEiv");
+    }
+
+    #[test]
+    fn try_new_errors() {
+	use super::{
+	    Type,
+	    Func,
+	    Scope,
+	    Component,
+	    UnqualifiedName,
+	    CtorKind,
+	    DtorKind,
+	    MangleError,
+	    Qualifiers,
+	    StdLib
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	assert_eq!(Func::try_new(String::from("ns::func"), vec![Type::Int, Type::Ellipsis]).map(|f| f.mangle()), Ok(String::from("_ZN2ns4funcEiz")));
+	assert_eq!(Func::try_new(String::from(""), vec![Type::Void]), Err(MangleError::EmptyName));
+	assert_eq!(Func::try_new(String::from("a::::b"), vec![Type::Void]), Err(MangleError::EmptyComponent(1)));
+	assert_eq!(Func::try_new(String::from("my func"), vec![Type::Void]), Err(MangleError::InvalidIdentifier(String::from("my func"))));
+	assert_eq!(Func::try_new(String::from("ns::2func"), vec![Type::Void]), Err(MangleError::InvalidIdentifier(String::from("2func"))));
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::named(String::from("a b"))]), Err(MangleError::InvalidIdentifier(String::from("a b"))));
+	assert_eq!(Func::try_new(String::from("f"), vec![]), Err(MangleError::NoParameters));
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::Void, Type::Int]), Err(MangleError::MisplacedVoid));
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::Ellipsis, Type::Int]), Err(MangleError::MisplacedEllipsis));
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::pointer(Type::Ellipsis)]), Err(MangleError::MisplacedEllipsis));
+	assert_eq!(Func::new(String::from("f"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST).validate(), Err(MangleError::QualifiedNonMember));
+	// St abbreviates std:: without nesting the name, leaving nowhere for qualifiers
+	let sort = Func::new(String::from("std::sort"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST);
+	assert_eq!(sort.clone().with_std_lib(StdLib::LibStdCxx).validate(), Err(MangleError::QualifiedNonMember));
+	assert_eq!(sort.with_std_lib(StdLib::LibCxx).mangle(), "_ZNKSt3__14sortEv");
+	let size = Func::new(String::from("std::vector::size"), vec![Type::Void]).with_qualifiers(Qualifiers::CONST).with_std_lib(StdLib::LibStdCxx);
+	assert_eq!(size.validate().map(|_| size.mangle()), Ok(String::from("_ZNKSt6vector4sizeEv")));
+	assert!(Func::try_new(String::from("Vec3::operator+"), vec![Type::pointer(Type::Void)]).is_ok());
+	let empty = Func::from_scope(Scope::from_components(vec![]), vec![Type::Void]);
+	assert_eq!(empty.validate(), Err(MangleError::EmptyName));
+	// still mangles without panicking, even though the symbol is meaningless
+	let _ = empty.mangle();
+	let ctor = Func::from_scope(Scope::from_components(vec![Component::from(UnqualifiedName::Ctor(CtorKind::Complete))]), vec![Type::Void]);
+	assert_eq!(ctor.validate(), Err(MangleError::MisplacedStructor));
+	let dtor = Func::from_scope(Scope::from_components(vec![Component::from(UnqualifiedName::Dtor(DtorKind::Base))]), vec![Type::Void]);
+	assert_eq!(dtor.validate(), Err(MangleError::MisplacedStructor));
+    }
+
+    #[test]
+    fn mangle_builtins() {
+	use super::{
+	    Type,
+	    Func,
+	    TemplateArg
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let f = Func::new(String::from("f"), vec![Type::Bool, Type::LongDouble, Type::Char8, Type::Char16, Type::Char32, Type::NullPtr]);
+	let g = Func::new(String::from("g"), vec![Type::Half, Type::FloatN(16), Type::FloatNx(32), Type::BFloat16, Type::BitInt(8), Type::UBitInt(128)]);
+	let h = Func::new(String::from("h"), vec![Type::Decimal32, Type::Decimal64, Type::Decimal128, Type::Auto, Type::DecltypeAuto]);
+	let v = Func::new(String::from("v"), vec![Type::Vendor(String::from("foo")), Type::pointer(Type::Vendor(String::from("foo")))]);
+	let b = Func::new(String::from("b"), vec![Type::Void]).with_template_args(vec![TemplateArg::Literal(Type::Bool, 1)]);
+	assert_eq!(&f.mangle(), "_Z1fbeDuDsDiDn");
+	assert_eq!(&g.mangle(), "_Z1gDhDF16_DF32xDF16bDB8_DU128_");
+	assert_eq!(&h.mangle(), "_Z1hDfDdDeDaDc");
+	assert_eq!(&v.mangle(), "_Z1vu3fooPS_");
+	assert_eq!(&b.mangle(), "_Z1bILb1EEvv");
+    }
+
+    #[test]
+    fn mangle_variables() {
+	use super::{
+	    Type,
+	    Var,
+	    TemplateArg,
+	    Scope,
+	    Component
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	assert_eq!(&Var::new(String::from("counter")).mangle(), "counter");
+	assert_eq!(&Var::new(String::from("ns::counter")).mangle(), "_ZN2ns7counterE");
+	assert_eq!(&Var::new(String::from("Foo::count")).mangle(), "_ZN3Foo5countE");
+	assert_eq!(&Var::new(String::from("pi")).with_template_args(vec![TemplateArg::Type(Type::Double)]).mangle(), "_Z2piIdE");
+	let x = Var::from_scope(Scope::from_components(vec![
+	    Component::new(String::from("a")),
+	    Component::new(String::from("B")).with_template_args(vec![TemplateArg::Type(Type::Int)]),
+	    Component::new(String::from("x"))
+	]));
+	assert_eq!(&x.mangle(), "_ZN1a1BIiE1xE");
+	assert!(Var::try_new(String::from("ns::")).is_err());
+    }
+
+    #[test]
+    fn mangle_static_locals() {
+	use super::{
+	    Type,
+	    Func,
+	    Var
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let func = || Func::new(String::from("func"), vec![Type::Void]);
+	let multi = || Func::new(String::from("multi"), vec![Type::Int]);
+	let f = Func::new(String::from("ns::f"), vec![Type::pointer(Type::named(String::from("ns::W")))]);
+	assert_eq!(&Var::local(func(), String::from("x")).mangle(), "_ZZ4funcvE1x");
+	assert_eq!(&Var::local(multi(), String::from("x")).with_discriminator(1).mangle(), "_ZZ5multiiE1x_0");
+	assert_eq!(&Var::local(multi(), String::from("x")).with_discriminator(2).mangle(), "_ZZ5multiiE1x_1");
+	assert_eq!(&Var::local(multi(), String::from("x")).with_discriminator(13).mangle(), "_ZZ5multiiE1x__12_");
+	assert_eq!(&Var::local(f, String::from("z")).mangle(), "_ZZN2ns1fEPNS_1WEE1z");
+    }
+
+    #[test]
+    fn mangle_internal_linkage() {
+	use super::{
+	    Type,
+	    Func,
+	    Var
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let helper = Func::new(String::from("(anonymous namespace)::helper"), vec![Type::Void]);
+	assert_eq!(&helper.mangle(), "_ZN12_GLOBAL__N_16helperEv");
+	assert_eq!(&helper.to_string(), "(anonymous namespace)::helper()");
+	assert_eq!(&Var::new(String::from("(anonymous namespace)::v")).mangle(), "_ZN12_GLOBAL__N_11vE");
+	assert_eq!(&Func::new(String::from("s"), vec![Type::Int]).with_internal_linkage().mangle(), "_ZL1si");
+	assert_eq!(&Func::new(String::from("n::h"), vec![Type::Void]).with_internal_linkage().mangle(), "_ZN1nL1hEv");
+	assert_eq!(&Var::new(String::from("sv")).with_internal_linkage().mangle(), "_ZL2sv");
+    }
+
+    #[test]
+    fn mangle_std() {
+	use super::{
+	    Type,
+	    Func,
+	    MangleError,
+	    Scope,
+	    Component,
+	    Qualifiers,
+	    TemplateArg
+	};
+	use alloc::string::String;
+	use alloc::vec::Vec;
+	use alloc::vec;
+	let std = |n: &str, args: Vec<TemplateArg>| Type::Named(Scope::new(String::from(n)).with_template_args(args));
+	let chars = || vec![TemplateArg::Type(Type::Char)];
+	let traits = || TemplateArg::Type(std("std::char_traits", chars()));
+	let string = std("std::basic_string", vec![TemplateArg::Type(Type::Char), traits(), TemplateArg::Type(std("std::allocator", chars()))]);
+	let stream = |n| Type::lvalue_ref(std(n, vec![TemplateArg::Type(Type::Char), traits()]));
+	let vector = std("std::vector", vec![TemplateArg::Type(Type::Int), TemplateArg::Type(std("std::allocator", vec![TemplateArg::Type(Type::Int)]))]);
+	assert_eq!(&Func::new(String::from("std::sort"), vec![Type::Void]).mangle(), "_ZSt4sortv");
+	assert_eq!(&Func::new(String::from("a"), vec![std("std::allocator", chars()), Type::pointer(std("std::allocator", chars()))]).mangle(), "_Z1aSaIcEPS_");
+	assert_eq!(&Func::new(String::from("b"), vec![Type::lvalue_ref(string.clone()), Type::pointer(Type::constant(string.clone()))]).mangle(), "_Z1bRSsPKSs");
+	assert_eq!(&Func::new(String::from("c"), vec![stream("std::basic_istream"), stream("std::basic_ostream"), stream("std::basic_iostream")]).mangle(), "_Z1cRSiRSoRSd");
+	assert_eq!(&Func::new(String::from("d"), vec![vector.clone(), Type::pointer(vector)]).mangle(), "_Z1dSt6vectorIiSaIiEEPS1_");
+	assert_eq!(&Func::new(String::from("e"), vec![std("std::char_traits", chars())]).mangle(), "_Z1eSt11char_traitsIcE");
+	let Type::Named(string) = string else {
+	    unreachable!()
+	};
+	let mut size = string.components().to_vec();
+	size.push(Component::new(String::from("size")));
+	let size = Func::from_scope(Scope::from_components(size), vec![Type::Void]).with_qualifiers(Qualifiers::CONST);
+	assert_eq!(&size.mangle(), "_ZNKSs4sizeEv");
+	// typedefs never appear in symbols, so they cannot be mangled as written
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::named(String::from("std::string"))]), Err(MangleError::StdTypedef(String::from("string"))));
+	assert_eq!(Func::try_new(String::from("f"), vec![Type::lvalue_ref(Type::named(String::from("std::ostream")))]), Err(MangleError::StdTypedef(String::from("ostream"))));
+	assert_eq!(Func::try_new(String::from("std::string::size"), vec![Type::Void]), Err(MangleError::StdTypedef(String::from("string"))));
+    }
+
+    #[test]
+    fn mangle_abi_tags() {
+	use super::{
+	    Type,
+	    Func,
+	    Var,
+	    Scope,
+	    TemplateArg
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let chars = || vec![TemplateArg::Type(Type::Char)];
+	let class = |n: &str| TemplateArg::Type(Type::Named(Scope::new(String::from(n)).with_template_args(chars())));
+	let string = || Type::Named(Scope::new(String::from("std::__cxx11::basic_string")).with_template_args(vec![TemplateArg::Type(Type::Char), class("std::char_traits"), class("std::allocator")]));
+	let a = || Type::named(String::from("A[abi:x]"));
+	let f = Func::new(String::from("f"), vec![Type::Void]).with_return(string());
+	assert_eq!(&f.mangle(), "_Z1fB5cxx11v");
+	assert_eq!(&f.to_string(), "f[abi:cxx11]()");
+	assert_eq!(&Func::new(String::from("f3"), vec![string()]).with_return(string()).mangle(), "_Z2f3NSt7__cxx1112basic_stringIcSt11char_traitsIcESaIcEEE");
+	assert_eq!(&Func::new(String::from("S::name"), vec![Type::Void]).with_return(string()).mangle(), "_ZN1S4nameB5cxx11Ev");
+	assert_eq!(&Func::new(String::from("k"), vec![Type::Void]).with_abi_tags(vec![String::from("t")]).with_return(a()).mangle(), "_Z1kB1tB1xv");
+	assert_eq!(&Func::new(String::from("f9"), vec![Type::Void]).with_return(Type::lvalue_ref(Type::constant(a()))).mangle(), "_Z2f9B1xv");
+	assert_eq!(&Func::new(String::from("f4"), vec![a()]).with_return(a()).mangle(), "_Z2f41AB1x");
+	assert_eq!(&Func::new(String::from("takeA"), vec![a(), Type::pointer(a())]).mangle(), "_Z5takeA1AB1xPS_");
+	assert_eq!(&Func::new(String::from("c[abi:y][abi:a]"), vec![Type::Void]).mangle(), "_Z1cB1aB1yv");
+	// the return type of templates is mangled, so its tags are not inherited
+	assert_eq!(&Func::new(String::from("tf"), vec![Type::Void]).with_template_args(vec![TemplateArg::Type(a())]).with_return(a()).mangle(), "_Z2tfI1AB1xES0_v");
+	assert_eq!(&Var::new(String::from("vs")).with_abi_tags(vec![String::from("cxx11")]).mangle(), "_Z2vsB5cxx11");
+	assert_eq!(Func::try_new(String::from("g[abi:1x]"), vec![Type::Void]), Err(super::MangleError::InvalidIdentifier(String::from("1x"))));
+    }
+
+    #[test]
+    fn mangle_std_lib() {
+	use super::{
+	    Type,
+	    Func,
+	    Scope,
+	    Component,
+	    Qualifiers,
+	    TemplateArg,
+	    StdLib,
+	    Var
+	};
+	use alloc::string::String;
+	use alloc::vec;
+	let std = |n: &str, args| Component::new(String::from(n)).with_template_args(args);
+	let named = |c| Type::Named(Scope::from_components(vec![Component::new(String::from("std")), c]));
+	let vector = std("vector", vec![TemplateArg::Type(Type::Int), TemplateArg::Type(named(std("allocator", vec![TemplateArg::Type(Type::Int)])))]);
+	let push_back = Func::from_scope(Scope::from_components(vec![Component::new(String::from("std")), vector, Component::new(String::from("push_back"))]), vec![Type::rvalue_ref(Type::Int)]);
+	assert_eq!(&push_back.clone().with_std_lib(StdLib::LibCxx).mangle(), "_ZNSt3__16vectorIiNS_9allocatorIiEEE9push_backEOi");
+	assert_eq!(&push_back.with_std_lib(StdLib::LibStdCxx).mangle(), "_ZNSt6vectorIiSaIiEE9push_backEOi");
+	let chars = || vec![TemplateArg::Type(Type::Char)];
+	let string = std("basic_string", vec![TemplateArg::Type(Type::Char), TemplateArg::Type(named(std("char_traits", chars()))), TemplateArg::Type(named(std("allocator", chars())))]);
+	let size = Func::from_scope(Scope::from_components(vec![Component::new(String::from("std")), string, Component::new(String::from("size"))]), vec![Type::Void]).with_qualifiers(Qualifiers::CONST);
+	assert_eq!(&size.clone().with_std_lib(StdLib::LibCxx).mangle(), "_ZNKSt3__112basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEE4sizeEv");
+	assert_eq!(&size.with_std_lib(StdLib::LibStdCxx).mangle(), "_ZNKSs4sizeEv");
+	// names already in std::__1 are left alone
+	assert_eq!(&Func::new(String::from("std::__1::sort"), vec![Type::Void]).with_std_lib(StdLib::LibCxx).mangle(), "_ZNSt3__14sortEv");
+	assert_eq!(&Func::new(String::from("std::sort"), vec![Type::Void]).with_std_lib(StdLib::LibCxx).mangle(), "_ZNSt3__14sortEv");
+	assert_eq!(&Var::new(String::from("std::cout")).with_std_lib(StdLib::LibCxx).mangle(), "_ZNSt3__14coutE");
+	let local = Var::local(Func::new(String::from("std::use_facet"), vec![Type::Void]).with_std_lib(StdLib::LibCxx), String::from("id"));
+	assert_eq!(&local.clone().mangle(), "_ZZNSt3__19use_facetEvE2id");
+	assert_eq!(&local.with_std_lib(StdLib::LibStdCxx).mangle(), "_ZZSt9use_facetvE2id");
+    }
+
+    #[test]
+    fn mangle_function_types() {
+	use super::{
+	    Type,
+	    Func,
+	    FunctionType,
+	    Qualifiers,
+	    RefQualifier,
+	    TemplateArg,
+	    MangleError
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let cb = Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::Int, Type::pointer(Type::Void)])));
+	let set_cb = Func::new(String::from("set_cb"), vec![cb]);
+	assert_eq!(&set_cb.mangle(), "_Z6set_cbPFviPvE");
+	assert_eq!(&set_cb.to_string(), "set_cb(void (*)(int, void*))");
+	let f = || Type::Function(FunctionType::new(Type::Void, vec![Type::Void]));
+	assert_eq!(&Func::new(String::from("a"), vec![Type::lvalue_ref(f()), Type::pointer(f())]).mangle(), "_Z1aRFvvEPS_");
+	let g = || Type::Function(FunctionType::new(Type::Int, vec![Type::Char]));
+	let e = Func::new(String::from("e"), vec![Type::pointer(Type::pointer(g())), Type::pointer(Type::constant(Type::pointer(g())))]);
+	assert_eq!(&e.mangle(), "_Z1ePPFicEPKS0_");
+	assert_eq!(&e.to_string(), "e(int (**)(char), int (* const*)(char))");
+	let h = Func::new(String::from("h"), vec![Type::pointer(f()), Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::Void]).noexcept()))]);
+	assert_eq!(&h.mangle(), "_Z1hPFvvEPDoFvvE");
+	assert_eq!(&h.to_string(), "h(void (*)(), void (*)() noexcept)");
+	assert_eq!(&Func::new(String::from("c"), vec![Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::Int]).extern_c()))]).mangle(), "_Z1cPFYviE");
+	let member = Type::Function(FunctionType::new(Type::Void, vec![Type::Int]).with_qualifiers(Qualifiers::CONST).with_ref_qualifier(RefQualifier::LValue));
+	let t = Func::new(String::from("g"), vec![Type::Void]).with_template_args(vec![TemplateArg::Type(member)]);
+	assert_eq!(&t.mangle(), "_Z1gIKFviREEvv");
+	assert_eq!(&t.to_string(), "void g<void (int) const &>()");
+	let bad = Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::Ellipsis, Type::Int])));
+	assert_eq!(Func::new(String::from("f"), vec![bad]).validate(), Err(MangleError::MisplacedEllipsis));
+	// function parameters are pointers to the function, also within function types
+	let callback = || Type::Function(FunctionType::new(Type::Void, vec![Type::Int]));
+	let fun = Func::new(String::from("fn"), vec![callback()]);
+	assert_eq!(&fun.mangle(), "_Z2fnPFviE");
+	assert_eq!(&fun.to_string(), "fn(void (*)(int))");
+	let fp = Func::new(String::from("fp"), vec![Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![callback(), Type::array(2, Type::Int)])))]);
+	assert_eq!(&fp.mangle(), "_Z2fpPFvPFviEPiE");
+	assert_eq!(&fp.to_string(), "fp(void (*)(void (*)(int), int*))");
+    }
+
+    #[test]
+    fn mangle_arrays() {
+	use super::{
+	    Type,
+	    Func,
+	    FunctionType,
+	    TemplateArg
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let a = Func::new(String::from("a"), vec![Type::lvalue_ref(Type::array(16, Type::Int))]);
+	assert_eq!(&a.mangle(), "_Z1aRA16_i");
+	assert_eq!(&a.to_string(), "a(int (&) [16])");
+	let grid = || Type::pointer(Type::array(3, Type::array(4, Type::Int)));
+	let b = Func::new(String::from("b"), vec![grid(), grid()]);
+	assert_eq!(&b.mangle(), "_Z1bPA3_A4_iS1_");
+	assert_eq!(&b.to_string(), "b(int (*) [3][4], int (*) [3][4])");
+	assert_eq!(&Func::new(String::from("c"), vec![Type::lvalue_ref(Type::array(5, Type::constant(Type::Char)))]).to_string(), "c(char const (&) [5])");
+	assert_eq!(&Func::new(String::from("d"), vec![Type::lvalue_ref(Type::unbounded_array(Type::Int))]).mangle(), "_Z1dRA_i");
+	let callbacks = Type::lvalue_ref(Type::array(2, Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::Int])))));
+	let e = Func::new(String::from("e"), vec![callbacks]);
+	assert_eq!(&e.mangle(), "_Z1eRA2_PFviE");
+	assert_eq!(&e.to_string(), "e(void (* (&) [2])(int))");
+	let g = Func::new(String::from("g"), vec![Type::Void]).with_template_args(vec![TemplateArg::Type(Type::unbounded_array(Type::Int))]);
+	assert_eq!(&g.mangle(), "_Z1gIA_iEvv");
+	assert_eq!(&g.to_string(), "void g<int []>()");
+	// array parameters are pointers to the element type
+	let arr = Func::new(String::from("arr"), vec![Type::array(16, Type::Int)]);
+	assert_eq!(&arr.mangle(), "_Z3arrPi");
+	assert_eq!(&arr.to_string(), "arr(int*)");
+	let arr2 = Func::new(String::from("arr2"), vec![Type::unbounded_array(Type::constant(Type::Int)), Type::pointer(Type::array(3, Type::Int))]);
+	assert_eq!(&arr2.mangle(), "_Z4arr2PKiPA3_i");
+    }
+
+    #[test]
+    fn mangle_member_pointers() {
+	use super::{
+	    Type,
+	    Func,
+	    FunctionType,
+	    Qualifiers,
+	    RefQualifier
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let widget = || Type::named(String::from("Widget"));
+	let method = |t| Type::member_pointer(widget(), Type::Function(t));
+	let a = Func::new(String::from("a"), vec![method(FunctionType::new(Type::Void, vec![Type::Int]))]);
+	assert_eq!(&a.mangle(), "_Z1aM6WidgetFviE");
+	assert_eq!(&a.to_string(), "a(void (Widget::*)(int))");
+	let b = Func::new(String::from("b"), vec![Type::member_pointer(widget(), Type::Int)]);
+	assert_eq!(&b.mangle(), "_Z1bM6Widgeti");
+	assert_eq!(&b.to_string(), "b(int Widget::*)");
+	let c = Func::new(String::from("c"), vec![
+	    method(FunctionType::new(Type::Void, vec![Type::Int]).with_qualifiers(Qualifiers::CONST).with_ref_qualifier(RefQualifier::LValue)),
+	    method(FunctionType::new(Type::Void, vec![Type::Int]).with_ref_qualifier(RefQualifier::RValue))
+	]);
+	assert_eq!(&c.mangle(), "_Z1cM6WidgetKFviREMS_FviOE");
+	assert_eq!(&c.to_string(), "c(void (Widget::*)(int) const &, void (Widget::*)(int) &&)");
+	let d = Func::new(String::from("d"), vec![Type::member_pointer(widget(), Type::Int), Type::pointer(widget()), Type::member_pointer(widget(), Type::constant(Type::Int))]);
+	assert_eq!(&d.mangle(), "_Z1dM6WidgetiPS_MS_Ki");
+	assert_eq!(&d.to_string(), "d(int Widget::*, Widget*, int const Widget::*)");
+	let e = Func::new(String::from("e"), vec![method(FunctionType::new(Type::Void, vec![Type::pointer(widget())])), Type::pointer(method(FunctionType::new(Type::Int, vec![Type::Void])))]);
+	assert_eq!(&e.mangle(), "_Z1eM6WidgetFvPS_EPMS_FivE");
+	assert_eq!(&e.to_string(), "e(void (Widget::*)(Widget*), int (Widget::**)())");
+	assert_eq!(&Func::new(String::from("f"), vec![method(FunctionType::new(Type::Void, vec![Type::Void]).noexcept())]).mangle(), "_Z1fM6WidgetDoFvvE");
+    }
+
+    #[test]
+    fn mangle_return_types() {
+	use super::{
+	    Type,
+	    Func,
+	    FunctionType,
+	    TemplateArg,
+	    CtorKind,
+	    MangleError
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let h = Func::new(String::from("h"), vec![Type::Int]).with_template_args(vec![TemplateArg::Type(Type::Char)]).with_return(Type::pointer(Type::Int));
+	assert_eq!(&h.mangle(), "_Z1hIcEPii");
+	assert_eq!(&h.to_string(), "int* h<char>(int)");
+	let f = Func::new(String::from("A::f"), vec![Type::Void]).with_template_args(vec![TemplateArg::Type(Type::Int)]).with_return(Type::pointer(Type::constant(Type::Char)));
+	assert_eq!(&f.mangle(), "_ZN1A1fIiEEPKcv");
+	assert_eq!(&f.to_string(), "char const* A::f<int>()");
+	let fp = Type::pointer(Type::Function(FunctionType::new(Type::Int, vec![Type::Int])));
+	let fp = Func::new(String::from("fp"), vec![Type::Int]).with_template_args(vec![TemplateArg::Type(Type::Char)]).with_return(fp);
+	assert_eq!(&fp.mangle(), "_Z2fpIcEPFiiEi");
+	// only function templates encode their return type
+	let plain = Func::new(String::from("f"), vec![Type::Int]);
+	assert_eq!(&plain.clone().with_return(Type::pointer(Type::Int)).mangle(), "_Z1fi");
+	assert_eq!(plain.clone().with_return(Type::Void), plain);
+	assert_eq!(plain.with_return(Type::array(4, Type::Int)).validate(), Err(MangleError::InvalidReturn));
+	let ctor = Func::constructor(String::from("A"), CtorKind::Complete, vec![Type::Void]);
+	assert_eq!(ctor.with_return(Type::Int).validate(), Err(MangleError::InvalidReturn));
+	let conversion = Func::conversion(String::from("A"), Type::pointer(Type::Int));
+	assert_eq!(conversion.clone().with_return(Type::pointer(Type::Int)).validate(), Ok(()));
+	assert_eq!(conversion.with_return(Type::Int).validate(), Err(MangleError::InvalidReturn));
+    }
+
+    #[test]
+    fn mangle_template_params() {
+	use super::{
+	    Type,
+	    ArrayBound,
+	    Func,
+	    FunctionType,
+	    Scope,
+	    TemplateArg,
+	    MangleError
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	use alloc::boxed::Box;
+	let int = || vec![TemplateArg::Type(Type::Int)];
+	let f = Func::new(String::from("f"), vec![Type::pointer(Type::TemplateParam(0))]).with_template_args(int());
+	assert_eq!(&f.mangle(), "_Z1fIiEvPT_");
+	assert_eq!(&f.to_string(), "void f<int>(int*)");
+	let max = Func::new(String::from("max"), vec![Type::TemplateParam(0), Type::TemplateParam(0)]).with_template_args(int()).with_return(Type::TemplateParam(0));
+	assert_eq!(&max.mangle(), "_Z3maxIiET_S0_S0_");
+	assert_eq!(&max.to_string(), "int max<int>(int, int)");
+	let b = || Type::TemplateParam(1);
+	let conv = Func::new(String::from("conv"), vec![Type::TemplateParam(0), Type::lvalue_ref(Type::constant(b())), Type::pointer(b())])
+	    .with_template_args(vec![TemplateArg::Type(Type::Int), TemplateArg::Type(Type::Double)])
+	    .with_return(b());
+	assert_eq!(&conv.mangle(), "_Z4convIidET0_T_RKS0_PS0_");
+	assert_eq!(&conv.to_string(), "double conv<int, double>(int, double const&, double*)");
+	let boxed = Type::Named(Scope::new(String::from("Box")).with_template_args(vec![TemplateArg::Type(Type::TemplateParam(0))]));
+	let put = Func::new(String::from("put"), vec![boxed, Type::TemplateParam(0)]).with_template_args(vec![TemplateArg::Type(Type::Char)]);
+	assert_eq!(&put.mangle(), "_Z3putIcEv3BoxIT_ES1_");
+	assert_eq!(&put.to_string(), "void put<char>(Box<char>, char)");
+	let g = Func::new(String::from("g"), vec![
+	    Type::pointer(Type::Function(FunctionType::new(Type::Void, vec![Type::TemplateParam(0)]))),
+	    Type::member_pointer(Type::named(String::from("S")), Type::TemplateParam(0))
+	]).with_template_args(vec![TemplateArg::Type(Type::Long)]);
+	assert_eq!(&g.mangle(), "_Z1gIlEvPFvT_EM1SS0_");
+	assert_eq!(&g.to_string(), "void g<long>(void (*)(long), long S::*)");
+	let fr = Func::new(String::from("fr"), vec![Type::rvalue_ref(Type::TemplateParam(0))]).with_template_args(vec![TemplateArg::Type(Type::lvalue_ref(Type::Int))]);
+	assert_eq!(&fr.mangle(), "_Z2frIRiEvOT_");
+	assert_eq!(&fr.to_string(), "void fr<int&>(int&)");
+	let fl = Func::new(String::from("fl"), vec![Type::lvalue_ref(Type::TemplateParam(0))]).with_template_args(vec![TemplateArg::Type(Type::rvalue_ref(Type::Int))]);
+	assert_eq!(&fl.mangle(), "_Z2flIOiEvRT_");
+	assert_eq!(&fl.to_string(), "void fl<int&&>(int&)");
+	let moved = Func::new(String::from("fm"), vec![Type::rvalue_ref(Type::TemplateParam(0))]).with_template_args(vec![TemplateArg::Type(Type::rvalue_ref(Type::Int))]);
+	assert_eq!(&moved.to_string(), "void fm<int&&>(int&&)");
+	let conversion = Func::conversion(String::from("S"), Type::pointer(Type::TemplateParam(0))).with_template_args(int());
+	assert_eq!(&conversion.mangle(), "_ZN1ScvPT_IiEEv");
+	assert_eq!(&conversion.to_string(), "S::operator int*<int>()");
+	// parameters must refer to type arguments of the function itself
+	assert_eq!(Func::new(String::from("h"), vec![Type::TemplateParam(0)]).validate(), Err(MangleError::InvalidTemplateParam(0)));
+	let literal = Func::new(String::from("h"), vec![Type::TemplateParam(1)]).with_template_args(vec![TemplateArg::Type(Type::Int), TemplateArg::Literal(Type::Int, 3)]);
+	assert_eq!(literal.validate(), Err(MangleError::InvalidTemplateParam(1)));
+	// array bounds refer to literal arguments instead
+	let bound = Type::lvalue_ref(Type::Array(ArrayBound::TemplateParam(0), Box::new(Type::Int)));
+	let fa = Func::new(String::from("fa"), vec![bound.clone()]).with_template_args(vec![TemplateArg::Literal(Type::Int, 4)]);
+	assert_eq!(&fa.mangle(), "_Z2faILi4EEvRAT__i");
+	assert_eq!(&fa.to_string(), "void fa<4>(int (&) [4])");
+	// the parameter in the bound is an expression, not a substitution candidate
+	let fb = Func::new(String::from("fb"), vec![bound.clone(), bound.clone(), Type::pointer(Type::Int)]).with_template_args(vec![TemplateArg::Literal(Type::Int, 2)]);
+	assert_eq!(&fb.mangle(), "_Z2fbILi2EEvRAT__iS1_Pi");
+	assert_eq!(Func::new(String::from("fa"), vec![bound.clone()]).with_template_args(int()).validate(), Err(MangleError::InvalidTemplateParam(0)));
+	let negative = Func::new(String::from("fa"), vec![bound]).with_template_args(vec![TemplateArg::Literal(Type::Int, -1)]);
+	assert_eq!(negative.validate(), Err(MangleError::InvalidTemplateParam(0)));
     }
 }
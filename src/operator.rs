@@ -0,0 +1,232 @@
+/*
+
+BSD 3-Clause License
+
+Copyright (c) 2025, Isaac Budzik
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+3. Neither the name of the copyright holder nor the names of its
+   contributors may be used to endorse or promote products derived from
+   this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+ */
+
+
+//! Operator names as used in the final component of a function name
+
+use core::fmt::{self, Display, Formatter};
+
+/// Overloadable C++ operator
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Operator {
+    /// new
+    New, // nw
+    /// new[]
+    NewArray, // na
+    /// delete
+    Delete, // dl
+    /// delete[]
+    DeleteArray, // da
+    /// co_await
+    CoAwait, // aw
+    /// + (unary)
+    UnaryPlus, // ps
+    /// - (unary)
+    Negate, // ng
+    /// & (unary)
+    AddressOf, // ad
+    /// * (unary)
+    Deref, // de
+    /// ~
+    Complement, // co
+    /// +
+    Plus, // pl
+    /// -
+    Minus, // mi
+    /// *
+    Multiply, // ml
+    /// /
+    Divide, // dv
+    /// %
+    Remainder, // rm
+    /// &
+    And, // an
+    /// |
+    Or, // or
+    /// ^
+    Xor, // eo
+    /// =
+    Assign, // aS
+    /// +=
+    PlusAssign, // pL
+    /// -=
+    MinusAssign, // mI
+    /// *=
+    MultiplyAssign, // mL
+    /// /=
+    DivideAssign, // dV
+    /// %=
+    RemainderAssign, // rM
+    /// &=
+    AndAssign, // aN
+    /// |=
+    OrAssign, // oR
+    /// ^=
+    XorAssign, // eO
+    /// <<
+    ShiftLeft, // ls
+    /// >>
+    ShiftRight, // rs
+    /// <<=
+    ShiftLeftAssign, // lS
+    /// >>=
+    ShiftRightAssign, // rS
+    /// ==
+    Equal, // eq
+    /// !=
+    NotEqual, // ne
+    /// <
+    Less, // lt
+    /// >
+    Greater, // gt
+    /// <=
+    LessEqual, // le
+    /// >=
+    GreaterEqual, // ge
+    /// <=>
+    Spaceship, // ss
+    /// !
+    Not, // nt
+    /// &&
+    LogicalAnd, // aa
+    /// ||
+    LogicalOr, // oo
+    /// ++
+    Increment, // pp
+    /// --
+    Decrement, // mm
+    /// ,
+    Comma, // cm
+    /// ->*
+    ArrowStar, // pm
+    /// ->
+    Arrow, // pt
+    /// ()
+    Call, // cl
+    /// []
+    Index // ix
+}
+
+/// operator, its mangled code and its C++ spelling, unary forms before binary
+const OPERATORS: &[(Operator, &str, &str)] = &[
+    (Operator::New, "nw", "new"),
+    (Operator::NewArray, "na", "new[]"),
+    (Operator::Delete, "dl", "delete"),
+    (Operator::DeleteArray, "da", "delete[]"),
+    (Operator::CoAwait, "aw", "co_await"),
+    (Operator::UnaryPlus, "ps", "+"),
+    (Operator::Negate, "ng", "-"),
+    (Operator::AddressOf, "ad", "&"),
+    (Operator::Deref, "de", "*"),
+    (Operator::Complement, "co", "~"),
+    (Operator::Plus, "pl", "+"),
+    (Operator::Minus, "mi", "-"),
+    (Operator::Multiply, "ml", "*"),
+    (Operator::Divide, "dv", "/"),
+    (Operator::Remainder, "rm", "%"),
+    (Operator::And, "an", "&"),
+    (Operator::Or, "or", "|"),
+    (Operator::Xor, "eo", "^"),
+    (Operator::Assign, "aS", "="),
+    (Operator::PlusAssign, "pL", "+="),
+    (Operator::MinusAssign, "mI", "-="),
+    (Operator::MultiplyAssign, "mL", "*="),
+    (Operator::DivideAssign, "dV", "/="),
+    (Operator::RemainderAssign, "rM", "%="),
+    (Operator::AndAssign, "aN", "&="),
+    (Operator::OrAssign, "oR", "|="),
+    (Operator::XorAssign, "eO", "^="),
+    (Operator::ShiftLeft, "ls", "<<"),
+    (Operator::ShiftRight, "rs", ">>"),
+    (Operator::ShiftLeftAssign, "lS", "<<="),
+    (Operator::ShiftRightAssign, "rS", ">>="),
+    (Operator::Equal, "eq", "=="),
+    (Operator::NotEqual, "ne", "!="),
+    (Operator::Less, "lt", "<"),
+    (Operator::Greater, "gt", ">"),
+    (Operator::LessEqual, "le", "<="),
+    (Operator::GreaterEqual, "ge", ">="),
+    (Operator::Spaceship, "ss", "<=>"),
+    (Operator::Not, "nt", "!"),
+    (Operator::LogicalAnd, "aa", "&&"),
+    (Operator::LogicalOr, "oo", "||"),
+    (Operator::Increment, "pp", "++"),
+    (Operator::Decrement, "mm", "--"),
+    (Operator::Comma, "cm", ","),
+    (Operator::ArrowStar, "pm", "->*"),
+    (Operator::Arrow, "pt", "->"),
+    (Operator::Call, "cl", "()"),
+    (Operator::Index, "ix", "[]")
+];
+
+impl Operator {
+    /// operator spelled sym as in operator+, binary if the symbol is also a unary operator
+    pub fn from_symbol(sym: &str) -> Option<Self> {
+	// binary forms come after the unary ones
+	OPERATORS.iter().rev().find(|o| o.2 == sym).map(|o| o.0)
+    }
+
+    /// unary form of an operator that can be both unary and binary
+    pub fn unary(self) -> Self {
+	match self {
+	    Operator::Plus => Operator::UnaryPlus,
+	    Operator::Minus => Operator::Negate,
+	    Operator::And => Operator::AddressOf,
+	    Operator::Multiply => Operator::Deref,
+	    o => o
+	}
+    }
+
+    /// <operator-name> code
+    pub(crate) fn code(self) -> &'static str {
+	OPERATORS.iter().find(|o| o.0 == self).map_or("", |o| o.1)
+    }
+
+    /// operator with the given mangled code
+    pub(crate) fn from_code(code: &[u8]) -> Option<Self> {
+	OPERATORS.iter().find(|o| o.1.as_bytes() == code).map(|o| o.0)
+    }
+}
+
+/// spelled as in operator+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	let sym = OPERATORS.iter().find(|o| o.0 == *self).map_or("", |o| o.2);
+	// keyword operators are separated from the operator keyword
+	if sym.starts_with(|c: char| c.is_ascii_alphabetic()) {
+	    write!(f, "operator {}", sym)
+	} else {
+	    write!(f, "operator{}", sym)
+	}
+    }
+}
@@ -0,0 +1,307 @@
+/*
+
+BSD 3-Clause License
+
+Copyright (c) 2025, Isaac Budzik
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+3. Neither the name of the copyright holder nor the names of its
+   contributors may be used to endorse or promote products derived from
+   this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+ */
+
+
+//! Special names for data and functions the compiler emits alongside classes
+
+use core::{
+    write,
+    fmt::{self, Display, Formatter, Write},
+};
+use alloc::string::String;
+use alloc::boxed::Box;
+
+use super::{
+    Func,
+    Mangler,
+    StdLib,
+    Type,
+    Var
+};
+
+/// Adjustment of this or of the returned pointer made by a thunk
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CallOffset {
+    /// fixed offset in bytes
+    NonVirtual(i64), // h <offset> _
+    /// fixed offset followed by an offset read from the vtable at the virtual offset
+    Virtual(i64, i64) // v <offset> _ <virtual offset> _
+}
+
+impl CallOffset {
+    /// <call-offset>
+    fn mangle(&self, s: &mut String) {
+	match self {
+	    CallOffset::NonVirtual(n) => {
+		s.push('h');
+		offset(s, *n);
+	    },
+	    CallOffset::Virtual(n, v) => {
+		s.push('v');
+		offset(s, *n);
+		offset(s, *v);
+	    }
+	}
+    }
+}
+
+/// <number> _ where negative numbers are prefixed by n
+fn offset(s: &mut String, n: i64) {
+    if n < 0 {
+	s.push('n');
+    }
+    let _ = write!(s, "{}_", n.unsigned_abs());
+}
+
+/// Special symbol, mangled as _ZT followed by its kind and what it belongs to
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum SpecialName {
+    /// virtual table
+    VTable(Type), // TV <type>
+    /// virtual table table
+    Vtt(Type), // TT <type>
+    /// typeinfo structure
+    TypeInfo(Type), // TI <type>
+    /// typeinfo name string
+    TypeInfoName(Type), // TS <type>
+    /// virtual function called through a base, adjusting this first
+    Thunk(CallOffset, Box<Func>), // Th or Tv <call-offset> <encoding>
+    /// virtual function with a covariant return type, adjusting this and the returned pointer
+    CovariantThunk(CallOffset, CallOffset, Box<Func>), // Tc <call-offset> <call-offset> <encoding>
+    /// guard variable of a static local or other lazily initialized variable
+    GuardVariable(Var), // GV <object name>
+    /// function returning the address of a thread_local variable
+    TlsWrapper(Var), // TW <object name>
+    /// function initializing a thread_local variable
+    TlsInit(Var) // TH <object name>
+}
+
+impl SpecialName {
+    /// virtual table of the class with the full C++ name
+    pub fn vtable(class: String) -> Self {
+	Self::VTable(Type::named(class))
+    }
+
+    /// virtual table table of the class with the full C++ name
+    pub fn vtt(class: String) -> Self {
+	Self::Vtt(Type::named(class))
+    }
+
+    /// typeinfo structure of the class with the full C++ name
+    pub fn typeinfo(class: String) -> Self {
+	Self::TypeInfo(Type::named(class))
+    }
+
+    /// typeinfo name string of the class with the full C++ name
+    pub fn typeinfo_name(class: String) -> Self {
+	Self::TypeInfoName(Type::named(class))
+    }
+
+    /// thunk to f adjusting this by offset
+    pub fn thunk(offset: CallOffset, f: Func) -> Self {
+	Self::Thunk(offset, Box::new(f))
+    }
+
+    /// covariant return thunk to f adjusting this and then the returned pointer
+    pub fn covariant_thunk(this: CallOffset, result: CallOffset, f: Func) -> Self {
+	Self::CovariantThunk(this, result, Box::new(f))
+    }
+
+    /// guard variable making the initialization of v happen once
+    pub fn guard_variable(v: Var) -> Self {
+	Self::GuardVariable(v)
+    }
+
+    /// Mangle special name according to Itanium C++ ABI
+    pub fn mangle(&self) -> String {
+	let std_lib = match self {
+	    SpecialName::Thunk(_, f) | SpecialName::CovariantThunk(_, _, f) => f.std_lib,
+	    SpecialName::GuardVariable(v) | SpecialName::TlsWrapper(v) | SpecialName::TlsInit(v) => v.std_lib(),
+	    _ => None
+	};
+	self.mangle_for(std_lib)
+    }
+
+    /// Mangle special name with names in std mangled for lib, instead of the standard
+    /// library of the function or variable it belongs to or the global one
+    pub fn mangle_with_std_lib(&self, lib: StdLib) -> String {
+	self.mangle_for(Some(lib))
+    }
+
+    fn mangle_for(&self, std_lib: Option<StdLib>) -> String {
+	let mut m = Mangler::new(std_lib);
+	match self {
+	    SpecialName::VTable(t) => {
+		m.s.push_str("TV");
+		t.mangle(&mut m);
+	    },
+	    SpecialName::Vtt(t) => {
+		m.s.push_str("TT");
+		t.mangle(&mut m);
+	    },
+	    SpecialName::TypeInfo(t) => {
+		m.s.push_str("TI");
+		t.mangle(&mut m);
+	    },
+	    SpecialName::TypeInfoName(t) => {
+		m.s.push_str("TS");
+		t.mangle(&mut m);
+	    },
+	    SpecialName::Thunk(o, f) => {
+		// the call offset already says whether the thunk is virtual
+		m.s.push('T');
+		o.mangle(&mut m.s);
+		f.encode(&mut m);
+	    },
+	    SpecialName::CovariantThunk(this, result, f) => {
+		m.s.push_str("Tc");
+		this.mangle(&mut m.s);
+		result.mangle(&mut m.s);
+		f.encode(&mut m);
+	    },
+	    SpecialName::GuardVariable(v) => {
+		m.s.push_str("GV");
+		v.encode(&mut m);
+	    },
+	    SpecialName::TlsWrapper(v) => {
+		m.s.push_str("TW");
+		v.encode(&mut m);
+	    },
+	    SpecialName::TlsInit(v) => {
+		m.s.push_str("TH");
+		v.encode(&mut m);
+	    }
+	}
+	m.s
+    }
+}
+
+/// as printed by c++filt, e.g. vtable for Foo
+impl Display for SpecialName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	match self {
+	    SpecialName::VTable(t) => write!(f, "vtable for {}", t),
+	    SpecialName::Vtt(t) => write!(f, "VTT for {}", t),
+	    SpecialName::TypeInfo(t) => write!(f, "typeinfo for {}", t),
+	    SpecialName::TypeInfoName(t) => write!(f, "typeinfo name for {}", t),
+	    SpecialName::Thunk(CallOffset::NonVirtual(_), func) => write!(f, "non-virtual thunk to {}", func),
+	    SpecialName::Thunk(CallOffset::Virtual(..), func) => write!(f, "virtual thunk to {}", func),
+	    SpecialName::CovariantThunk(_, _, func) => write!(f, "covariant return thunk to {}", func),
+	    SpecialName::GuardVariable(v) => write!(f, "guard variable for {}", v),
+	    SpecialName::TlsWrapper(v) => write!(f, "TLS wrapper function for {}", v),
+	    SpecialName::TlsInit(v) => write!(f, "TLS init function for {}", v)
+	}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn mangle_special() {
+	use super::SpecialName;
+	use crate::{
+	    Type,
+	    StdLib
+	};
+	use alloc::string::{String, ToString};
+	assert_eq!(&SpecialName::vtable(String::from("Foo")).mangle(), "_ZTV3Foo");
+	assert_eq!(&SpecialName::vtt(String::from("ns::A")).mangle(), "_ZTTN2ns1AE");
+	assert_eq!(&SpecialName::typeinfo(String::from("Foo")).mangle(), "_ZTI3Foo");
+	assert_eq!(&SpecialName::typeinfo_name(String::from("ns::A")).mangle(), "_ZTSN2ns1AE");
+	assert_eq!(&SpecialName::TypeInfo(Type::pointer(Type::constant(Type::Char))).mangle(), "_ZTIPKc");
+	assert_eq!(&SpecialName::vtable(String::from("ns::A")).to_string(), "vtable for ns::A");
+	let ios_base = SpecialName::typeinfo(String::from("std::ios_base"));
+	assert_eq!(&ios_base.mangle_with_std_lib(StdLib::LibCxx), "_ZTINSt3__18ios_baseE");
+	assert_eq!(&ios_base.mangle_with_std_lib(StdLib::LibStdCxx), "_ZTISt8ios_base");
+    }
+
+    #[test]
+    fn mangle_thunks() {
+	use super::{
+	    SpecialName,
+	    CallOffset
+	};
+	use crate::{
+	    Type,
+	    Func,
+	    DtorKind
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let h = SpecialName::thunk(CallOffset::NonVirtual(-16), Func::new(String::from("C::h"), vec![Type::Void]));
+	let d = SpecialName::thunk(CallOffset::Virtual(0, -24), Func::destructor(String::from("ns::A"), DtorKind::Deleting));
+	let c = SpecialName::covariant_thunk(CallOffset::NonVirtual(-16), CallOffset::NonVirtual(16), Func::new(String::from("C::f"), vec![Type::Void]));
+	let v = SpecialName::covariant_thunk(CallOffset::Virtual(0, -24), CallOffset::Virtual(0, -32), Func::new(String::from("D::v"), vec![Type::Void]));
+	assert_eq!(&h.mangle(), "_ZThn16_N1C1hEv");
+	assert_eq!(&d.mangle(), "_ZTv0_n24_N2ns1AD0Ev");
+	assert_eq!(&c.mangle(), "_ZTchn16_h16_N1C1fEv");
+	assert_eq!(&v.mangle(), "_ZTcv0_n24_v0_n32_N1D1vEv");
+	assert_eq!(&h.to_string(), "non-virtual thunk to C::h()");
+    }
+
+    #[test]
+    fn mangle_guard_variables() {
+	use super::SpecialName;
+	use crate::{
+	    Type,
+	    Func,
+	    Var
+	};
+	use alloc::string::{String, ToString};
+	use alloc::vec;
+	let x = Var::local(Func::new(String::from("func"), vec![Type::Void]), String::from("x"));
+	let y = Var::local(Func::new(String::from("multi"), vec![Type::Int]), String::from("x")).with_discriminator(1);
+	assert_eq!(&SpecialName::guard_variable(x.clone()).mangle(), "_ZGVZ4funcvE1x");
+	assert_eq!(&SpecialName::guard_variable(y).mangle(), "_ZGVZ5multiiE1x_0");
+	assert_eq!(&SpecialName::guard_variable(Var::new(String::from("iv"))).mangle(), "_ZGV2iv");
+	assert_eq!(&SpecialName::guard_variable(x).to_string(), "guard variable for func()::x");
+    }
+
+    #[test]
+    fn mangle_tls() {
+	use crate::{
+	    Var,
+	    StdLib
+	};
+	use alloc::string::{String, ToString};
+	let tl = Var::new(String::from("ns::tl"));
+	assert_eq!(&tl.tls_wrapper().mangle(), "_ZTWN2ns2tlE");
+	assert_eq!(&tl.tls_init().mangle(), "_ZTHN2ns2tlE");
+	assert_eq!(&Var::new(String::from("tl")).tls_wrapper().mangle(), "_ZTW2tl");
+	assert_eq!(&tl.tls_wrapper().to_string(), "TLS wrapper function for ns::tl");
+	let errno = Var::new(String::from("std::tls_errno")).with_std_lib(StdLib::LibCxx);
+	assert_eq!(&errno.tls_init().mangle(), "_ZTHNSt3__19tls_errnoE");
+	assert_eq!(&errno.tls_wrapper().mangle_with_std_lib(StdLib::LibStdCxx), "_ZTWSt9tls_errno");
+    }
+}